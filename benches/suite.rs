@@ -142,7 +142,7 @@ fn bench_new(b: &mut Bencher) {
         assert_eq!(e.name.as_str(), "foo");
         assert_eq!(e.attributes.len(), 0);
         assert_eq!(e.children.len(), 0);
-        assert_eq!(e.text, None);
+        assert_eq!(e.get_text(), None);
     });
 }
 
@@ -0,0 +1,326 @@
+//! A read-only element tree that borrows its strings from the input
+//!
+//! [`BorrowedElement::parse`](struct.BorrowedElement.html#method.parse) parses a document
+//! held in memory without copying names, attribute values or text: they are slices of the
+//! input, except where entity references had to be decoded.  This avoids most of the
+//! allocations made by [`Element::parse`](../struct.Element.html#method.parse), which helps
+//! when a large document is parsed once and then only read.
+//!
+//! The parser is much simpler than that of `xml-rs`:
+//!
+//! * names are kept exactly as written, including any prefix; namespaces are not resolved
+//! * comments and processing instructions are skipped, as is text consisting only of
+//!   whitespace
+//! * document type declarations are not supported, so only the predefined entities and
+//!   character references can be used
+//!
+//! ```
+//! use xmltree::borrowed::BorrowedElement;
+//!
+//! let data = r#"<names><name first="bob">Bob &amp; Co</name></names>"#;
+//! let names = BorrowedElement::parse(data).unwrap();
+//! let name = names.get_child("name").unwrap();
+//! assert_eq!(name.get_attribute("first"), Some("bob"));
+//! assert_eq!(name.get_text().unwrap(), "Bob & Co");
+//! ```
+
+use std::borrow::Cow;
+
+use xml::common::TextPosition;
+
+use super::util::{decode_reference, is_valid_xml_name, unescape_xml};
+use super::{Element, ParseError, XMLNode};
+
+/// An element whose strings are borrowed from the parsed input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorrowedElement<'a> {
+	/// The name of the element, including its prefix if it has one
+	pub name: &'a str,
+
+	/// The attributes of the element, in the order they were written
+	pub attributes: Vec<(&'a str, Cow<'a, str>)>,
+
+	/// Children
+	pub children: Vec<BorrowedNode<'a>>,
+}
+
+/// A node in a [`BorrowedElement`](struct.BorrowedElement.html) tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BorrowedNode<'a> {
+	/// A child element
+	Element(BorrowedElement<'a>),
+	/// Character data, with entity references decoded
+	Text(Cow<'a, str>),
+	/// A CDATA section
+	CData(&'a str),
+}
+
+impl<'a> BorrowedElement<'a> {
+	/// Parses the root element of a document
+	///
+	/// An XML declaration, comments and processing instructions may come before the root
+	/// element.  Parsing stops at the end of the root element.  Elements may be nested at
+	/// most 256 levels deep, counting the root element as level 1.
+	pub fn parse(input: &'a str) -> Result<BorrowedElement<'a>, ParseError> {
+		let mut parser = Parser { input, pos: 0 };
+		parser.eat("\u{feff}");
+		loop {
+			parser.skip_whitespace();
+			if parser.eat("<?") {
+				parser.skip_past("?>")?;
+			} else if parser.eat("<!--") {
+				parser.skip_past("-->")?;
+			} else if parser.rest().starts_with("<!") {
+				return parser.error("document type declarations are not supported");
+			} else if parser.rest().starts_with('<') {
+				return parser.element(1);
+			} else {
+				return parser.error("expected the root element");
+			}
+		}
+	}
+
+	/// Returns the value of the attribute with the given name, if any.
+	pub fn get_attribute(&self, name: &str) -> Option<&str> {
+		self.attributes
+			.iter()
+			.find(|(k, _)| *k == name)
+			.map(|(_, v)| v.as_ref())
+	}
+
+	/// Returns an iterator over the child elements, skipping any other kinds of nodes.
+	pub fn child_elements(&self) -> impl Iterator<Item = &BorrowedElement<'a>> {
+		self.children.iter().filter_map(|node| match node {
+			BorrowedNode::Element(e) => Some(e),
+			_ => None,
+		})
+	}
+
+	/// Find a child element with the given name and return a reference to it.
+	pub fn get_child(&self, name: &str) -> Option<&BorrowedElement<'a>> {
+		self.child_elements().find(|e| e.name == name)
+	}
+
+	/// Returns the concatenation of all text and CDATA child nodes, or `None` if there are
+	/// none.
+	pub fn get_text(&self) -> Option<Cow<'_, str>> {
+		let mut texts = self.children.iter().filter_map(|node| match node {
+			BorrowedNode::Text(s) => Some(s.as_ref()),
+			BorrowedNode::CData(s) => Some(*s),
+			BorrowedNode::Element(_) => None,
+		});
+
+		let first = texts.next()?;
+		match texts.next() {
+			None => Some(Cow::Borrowed(first)),
+			Some(second) => {
+				let mut text = String::from(first);
+				text.push_str(second);
+				text.extend(texts);
+				Some(Cow::Owned(text))
+			}
+		}
+	}
+
+	/// Copies this tree into an owned [`Element`](../struct.Element.html)
+	///
+	/// Names with a prefix are split into `prefix` and `name`, but as namespaces are not
+	/// resolved, `namespace` is not set and `xmlns` declarations are kept as attributes.
+	pub fn to_element(&self) -> Element {
+		let mut elem = match self.name.split_once(':') {
+			Some((prefix, name)) => {
+				let mut elem = Element::new(name);
+				elem.prefix = Some(prefix.to_owned());
+				elem
+			}
+			None => Element::new(self.name),
+		};
+		for (k, v) in &self.attributes {
+			elem.attributes.insert((*k).to_owned(), v.as_ref().to_owned());
+		}
+		elem.children = self
+			.children
+			.iter()
+			.map(|node| match node {
+				BorrowedNode::Element(e) => XMLNode::Element(e.to_element()),
+				BorrowedNode::Text(s) => XMLNode::Text(s.as_ref().to_owned()),
+				BorrowedNode::CData(s) => XMLNode::CData((*s).to_owned()),
+			})
+			.collect();
+		elem
+	}
+}
+
+/// The maximum nesting depth of elements, which keeps the recursive parser (and the
+/// recursive `Drop` of the tree) from overflowing the stack
+const MAX_DEPTH: usize = 256;
+
+struct Parser<'a> {
+	input: &'a str,
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn rest(&self) -> &'a str {
+		&self.input[self.pos..]
+	}
+
+	fn eat(&mut self, s: &str) -> bool {
+		if self.rest().starts_with(s) {
+			self.pos += s.len();
+			true
+		} else {
+			false
+		}
+	}
+
+	fn expect(&mut self, s: &'static str, msg: &'static str) -> Result<(), ParseError> {
+		if self.eat(s) {
+			Ok(())
+		} else {
+			self.error(msg)
+		}
+	}
+
+	fn skip_whitespace(&mut self) {
+		let rest = self.rest();
+		self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\r', '\n']).len();
+	}
+
+	/// Skips past the next occurrence of `end`, returning the text before it
+	fn skip_past(&mut self, end: &str) -> Result<&'a str, ParseError> {
+		match self.rest().find(end) {
+			Some(i) => {
+				let skipped = &self.rest()[..i];
+				self.pos += i + end.len();
+				Ok(skipped)
+			}
+			None => self.error("unexpected end of input"),
+		}
+	}
+
+	fn error<T>(&self, msg: &'static str) -> Result<T, ParseError> {
+		let before = &self.input[..self.pos];
+		let row = before.matches('\n').count() as u64;
+		let column = before.rsplit('\n').next().map_or(0, |line| line.chars().count()) as u64;
+		let pos = TextPosition { row, column };
+		Err(ParseError::MalformedXml(xml::reader::Error::from((&pos, msg))))
+	}
+
+	/// Decodes the references in text or an attribute value starting at `start`, failing on
+	/// a `&` that does not start a predefined entity or character reference
+	fn unescape(&mut self, start: usize, text: &'a str) -> Result<Cow<'a, str>, ParseError> {
+		for (i, _) in text.match_indices('&') {
+			let rest = &text[i + 1..];
+			if rest.find(';').and_then(|end| decode_reference(&rest[..end])).is_none() {
+				self.pos = start + i;
+				return self.error("invalid reference");
+			}
+		}
+		Ok(unescape_xml(text))
+	}
+
+	fn name(&mut self) -> Result<&'a str, ParseError> {
+		let rest = self.rest();
+		let len = rest
+			.find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '=' | '<' | '"' | '\''))
+			.unwrap_or(rest.len());
+		let name = &rest[..len];
+		if !is_valid_xml_name(name) {
+			return self.error("invalid name");
+		}
+		self.pos += len;
+		Ok(name)
+	}
+
+	/// Parses an element at the given depth, starting at its `<`
+	fn element(&mut self, depth: usize) -> Result<BorrowedElement<'a>, ParseError> {
+		if depth > MAX_DEPTH {
+			return self.error("nesting too deep");
+		}
+		self.expect("<", "expected `<`")?;
+		let mut elem = BorrowedElement {
+			name: self.name()?,
+			attributes: Vec::new(),
+			children: Vec::new(),
+		};
+
+		loop {
+			let had_whitespace = {
+				let pos = self.pos;
+				self.skip_whitespace();
+				self.pos > pos
+			};
+			if self.eat("/>") {
+				return Ok(elem);
+			}
+			if self.eat(">") {
+				break;
+			}
+			if !had_whitespace {
+				return self.error("expected whitespace before an attribute");
+			}
+
+			let name = self.name()?;
+			if elem.attributes.iter().any(|(k, _)| *k == name) {
+				return self.error("duplicate attribute");
+			}
+			self.skip_whitespace();
+			self.expect("=", "expected `=`")?;
+			self.skip_whitespace();
+			let quote = if self.eat("\"") {
+				"\""
+			} else if self.eat("'") {
+				"'"
+			} else {
+				return self.error("expected a quoted attribute value");
+			};
+			let start = self.pos;
+			let value = self.skip_past(quote)?;
+			if value.contains('<') {
+				return self.error("`<` is not allowed in attribute values");
+			}
+			let value = self.unescape(start, value)?;
+			elem.attributes.push((name, value));
+		}
+
+		loop {
+			if self.eat("</") {
+				let start = self.pos;
+				if self.name()? != elem.name {
+					self.pos = start;
+					return self.error("end tag does not match start tag");
+				}
+				self.skip_whitespace();
+				self.expect(">", "expected `>`")?;
+				return Ok(elem);
+			} else if self.eat("<![CDATA[") {
+				let data = self.skip_past("]]>")?;
+				elem.children.push(BorrowedNode::CData(data));
+			} else if self.eat("<!--") {
+				self.skip_past("-->")?;
+			} else if self.eat("<?") {
+				self.skip_past("?>")?;
+			} else if self.rest().starts_with('<') {
+				let child = self.element(depth + 1)?;
+				elem.children.push(BorrowedNode::Element(child));
+			} else if self.rest().is_empty() {
+				return self.error("unexpected end of input");
+			} else {
+				let start = self.pos;
+				let rest = self.rest();
+				let len = rest.find('<').unwrap_or(rest.len());
+				let text = &rest[..len];
+				if let Some(i) = text.find("]]>") {
+					self.pos = start + i;
+					return self.error("`]]>` is not allowed in text");
+				}
+				let text = self.unescape(start, text)?;
+				self.pos = start + len;
+				if !text.trim_start_matches([' ', '\t', '\r', '\n']).is_empty() {
+					elem.children.push(BorrowedNode::Text(text));
+				}
+			}
+		}
+	}
+}
@@ -0,0 +1,303 @@
+//! Structural comparison of element trees
+//!
+//! [`diff`](fn.diff.html) compares an expected tree against an actual one and lists every
+//! difference it finds, which makes for much more useful test failures than a plain
+//! `assert_eq!` on two large trees.
+//!
+//! ```
+//! use xmltree::Element;
+//! use xmltree::diff::{diff, Difference};
+//!
+//! let a: Element = r#"<a x="1"><b/></a>"#.parse().unwrap();
+//! let b: Element = r#"<a x="2"><b/></a>"#.parse().unwrap();
+//! assert_eq!(
+//!     diff(&a, &b),
+//!     vec![Difference::AttributeValueMismatch {
+//!         path: vec!["a".to_owned()],
+//!         name: "x".to_owned(),
+//!         expected: "1".to_owned(),
+//!         actual: "2".to_owned(),
+//!     }]
+//! );
+//! ```
+//!
+//! The other functions in this module compare elements for equality in ways that are
+//! looser than `==`.
+
+use std::fmt;
+
+use super::{Element, XMLNode};
+
+/// A single difference between two element trees
+///
+/// Each variant holds the `path` to the element where the difference was found, as the
+/// names of the elements from the root down to (and including) that element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)]
+pub enum Difference {
+	/// The elements have different names
+	ElementNameMismatch {
+		path: Vec<String>,
+		expected: String,
+		actual: String,
+	},
+	/// An attribute of the expected element is missing from the actual one
+	AttributeMissing { path: Vec<String>, name: String },
+	/// The actual element has an attribute that the expected one does not
+	ExtraAttribute { path: Vec<String>, name: String },
+	/// An attribute has different values
+	AttributeValueMismatch {
+		path: Vec<String>,
+		name: String,
+		expected: String,
+		actual: String,
+	},
+	/// The elements have different text, as returned by
+	/// [`Element::get_text_or_empty`](../struct.Element.html#method.get_text_or_empty)
+	TextMismatch {
+		path: Vec<String>,
+		expected: String,
+		actual: String,
+	},
+	/// The elements have a different number of child elements
+	ChildCountMismatch {
+		path: Vec<String>,
+		expected: usize,
+		actual: usize,
+	},
+	/// The actual element has a child element that the expected one does not
+	ExtraChild { path: Vec<String>, element: Element },
+	/// A child element of the expected element is missing from the actual one
+	MissingChild { path: Vec<String>, name: String },
+}
+
+impl Difference {
+	/// Returns the path to the element where this difference was found
+	pub fn path(&self) -> &[String] {
+		match *self {
+			Difference::ElementNameMismatch { ref path, .. }
+			| Difference::AttributeMissing { ref path, .. }
+			| Difference::ExtraAttribute { ref path, .. }
+			| Difference::AttributeValueMismatch { ref path, .. }
+			| Difference::TextMismatch { ref path, .. }
+			| Difference::ChildCountMismatch { ref path, .. }
+			| Difference::ExtraChild { ref path, .. }
+			| Difference::MissingChild { ref path, .. } => path,
+		}
+	}
+}
+
+impl fmt::Display for Difference {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "/{}: ", self.path().join("/"))?;
+		match *self {
+			Difference::ElementNameMismatch {
+				ref expected,
+				ref actual,
+				..
+			} => write!(f, "expected element <{}>, found <{}>", expected, actual),
+			Difference::AttributeMissing { ref name, .. } => write!(f, "missing attribute {:?}", name),
+			Difference::ExtraAttribute { ref name, .. } => write!(f, "unexpected attribute {:?}", name),
+			Difference::AttributeValueMismatch {
+				ref name,
+				ref expected,
+				ref actual,
+				..
+			} => write!(f, "expected attribute {}={:?}, found {:?}", name, expected, actual),
+			Difference::TextMismatch {
+				ref expected,
+				ref actual,
+				..
+			} => write!(f, "expected text {:?}, found {:?}", expected, actual),
+			Difference::ChildCountMismatch { expected, actual, .. } => {
+				write!(f, "expected {} child elements, found {}", expected, actual)
+			}
+			Difference::ExtraChild { ref element, .. } => write!(f, "unexpected child <{}>", element.name),
+			Difference::MissingChild { ref name, .. } => write!(f, "missing child <{}>", name),
+		}
+	}
+}
+
+/// Compares the `expected` tree against the `actual` one, returning all differences found.
+///
+/// Child elements are compared pairwise, in order.  Comments, processing instructions and
+/// the namespaces of elements are not compared.  An empty result means the trees are
+/// essentially equal.
+pub fn diff(expected: &Element, actual: &Element) -> Vec<Difference> {
+	let mut differences = Vec::new();
+	let mut path = Vec::new();
+	diff_element(expected, actual, &mut path, &mut differences);
+	differences
+}
+
+fn diff_element(expected: &Element, actual: &Element, path: &mut Vec<String>, out: &mut Vec<Difference>) {
+	path.push(expected.name.clone());
+
+	if expected.name != actual.name {
+		out.push(Difference::ElementNameMismatch {
+			path: path.clone(),
+			expected: expected.name.clone(),
+			actual: actual.name.clone(),
+		});
+		path.pop();
+		return;
+	}
+
+	let expected_attrs = attributes(expected);
+	let actual_attrs = attributes(actual);
+	for (name, value) in &expected_attrs {
+		match actual_attrs.iter().find(|(n, _)| n == name) {
+			None => out.push(Difference::AttributeMissing {
+				path: path.clone(),
+				name: name.clone(),
+			}),
+			Some((_, actual_value)) if actual_value != value => out.push(Difference::AttributeValueMismatch {
+				path: path.clone(),
+				name: name.clone(),
+				expected: (*value).to_owned(),
+				actual: (*actual_value).to_owned(),
+			}),
+			Some(_) => {}
+		}
+	}
+	for (name, _) in &actual_attrs {
+		if !expected_attrs.iter().any(|(n, _)| n == name) {
+			out.push(Difference::ExtraAttribute {
+				path: path.clone(),
+				name: name.clone(),
+			});
+		}
+	}
+
+	let expected_text = expected.get_text_or_empty();
+	let actual_text = actual.get_text_or_empty();
+	if expected_text != actual_text {
+		out.push(Difference::TextMismatch {
+			path: path.clone(),
+			expected: expected_text.into_owned(),
+			actual: actual_text.into_owned(),
+		});
+	}
+
+	let expected_children: Vec<&Element> = expected.child_elements().collect();
+	let actual_children: Vec<&Element> = actual.child_elements().collect();
+	if expected_children.len() != actual_children.len() {
+		out.push(Difference::ChildCountMismatch {
+			path: path.clone(),
+			expected: expected_children.len(),
+			actual: actual_children.len(),
+		});
+	}
+	for (e, a) in expected_children.iter().zip(&actual_children) {
+		diff_element(e, a, path, out);
+	}
+	for e in expected_children.iter().skip(actual_children.len()) {
+		out.push(Difference::MissingChild {
+			path: path.clone(),
+			name: e.name.clone(),
+		});
+	}
+	for a in actual_children.iter().skip(expected_children.len()) {
+		out.push(Difference::ExtraChild {
+			path: path.clone(),
+			element: (*a).clone(),
+		});
+	}
+
+	path.pop();
+}
+
+/// Lists all attributes of an element, with namespaced ones under their qualified name
+fn attributes(e: &Element) -> Vec<(String, &str)> {
+	let plain = e.attributes.iter().map(|(k, v)| (k.clone(), v.as_str()));
+	let namespaced = e.attributes_ns.iter().map(|(k, v)| (k.to_string(), v.as_str()));
+	plain.chain(namespaced).collect()
+}
+
+/// Returns the namespace URI of an element, resolving its prefix if the namespace is not set
+fn resolved_namespace(e: &Element) -> Option<&str> {
+	match (e.namespace.as_deref(), e.prefix.as_deref()) {
+		(Some(ns), _) => Some(ns),
+		(None, Some(prefix)) => e.namespace_for_prefix(prefix),
+		(None, None) => None,
+	}
+}
+
+/// Compares two elements, ignoring their children and the prefixes used for namespaces
+///
+/// The elements are equal if they have the same local name and namespace URI, and the same
+/// attributes, with namespaced attributes compared by namespace URI and local name.  The
+/// namespace declarations of the elements are not compared.
+pub fn namespace_equal(a: &Element, b: &Element) -> bool {
+	fn namespaced_attributes(e: &Element) -> Vec<(Option<&str>, &str, &str)> {
+		let mut attributes: Vec<_> = e
+			.attributes_ns
+			.iter()
+			.map(|(k, v)| (k.namespace.as_deref(), k.local_name.as_str(), v.as_str()))
+			.collect();
+		attributes.sort();
+		attributes
+	}
+
+	a.name == b.name
+		&& resolved_namespace(a) == resolved_namespace(b)
+		&& a.attributes == b.attributes
+		&& namespaced_attributes(a) == namespaced_attributes(b)
+}
+
+/// Compares two element trees, ignoring the prefixes used for namespaces
+///
+/// Each pair of elements is compared with [`namespace_equal`](fn.namespace_equal.html), and
+/// all other child nodes must be identical.
+pub fn deep_namespace_equal(a: &Element, b: &Element) -> bool {
+	namespace_equal(a, b)
+		&& a.children.len() == b.children.len()
+		&& a.children.iter().zip(&b.children).all(|pair| match pair {
+			(XMLNode::Element(a), XMLNode::Element(b)) => deep_namespace_equal(a, b),
+			(a, b) => a == b,
+		})
+}
+
+/// Compares two element trees, ignoring leading and trailing whitespace in text
+///
+/// Elements are equal if they have the same name, namespace information and attributes,
+/// their text (as returned by [`Element::get_text`](../struct.Element.html#method.get_text))
+/// is the same after trimming whitespace, and their child elements are equal in the same
+/// way.  This treats a document the same before and after indentation was added or
+/// removed.  Comments and processing instructions are not compared.
+pub fn deep_equal_ignoring_whitespace(a: &Element, b: &Element) -> bool {
+	a.name == b.name
+		&& a.prefix == b.prefix
+		&& a.namespace == b.namespace
+		&& a.namespaces == b.namespaces
+		&& a.attributes == b.attributes
+		&& a.attributes_ns == b.attributes_ns
+		&& a.get_text_or_empty().trim() == b.get_text_or_empty().trim()
+		&& a.child_count() == b.child_count()
+		&& a.child_elements()
+			.zip(b.child_elements())
+			.all(|(a, b)| deep_equal_ignoring_whitespace(a, b))
+}
+
+/// Compares two elements by name and attributes only, ignoring their children (and so their
+/// text)
+///
+/// As with `==`, the order of the attributes does not matter.
+pub fn shallow_equal(a: &Element, b: &Element) -> bool {
+	a.name == b.name
+		&& a.prefix == b.prefix
+		&& a.namespace == b.namespace
+		&& a.attributes == b.attributes
+		&& a.attributes_ns == b.attributes_ns
+}
+
+/// Compares two element trees by structure, names and attributes, ignoring text and all
+/// other kinds of nodes
+///
+/// Each pair of elements is compared with [`shallow_equal`](fn.shallow_equal.html), and
+/// their child elements are compared in order.
+pub fn attrs_equal(a: &Element, b: &Element) -> bool {
+	shallow_equal(a, b)
+		&& a.child_count() == b.child_count()
+		&& a.child_elements().zip(b.child_elements()).all(|(a, b)| attrs_equal(a, b))
+}
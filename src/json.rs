@@ -0,0 +1,110 @@
+//! Conversion between elements and a simple JSON structure, see `Element::to_json_value`
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use super::{Element, XMLNode};
+
+/// Errors that can occur converting a JSON value into an `Element`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonConvertError {
+	/// An element was not a JSON object
+	ExpectedObject,
+	/// An element had no `name`, or it was not a string
+	MissingName,
+	/// The field with the given name did not have the expected type
+	InvalidField(String),
+}
+
+impl fmt::Display for JsonConvertError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			JsonConvertError::ExpectedObject => write!(f, "Expected an object"),
+			JsonConvertError::MissingName => write!(f, "Missing element name"),
+			JsonConvertError::InvalidField(ref name) => write!(f, "Invalid field {:?}", name),
+		}
+	}
+}
+
+impl std::error::Error for JsonConvertError {}
+
+impl Element {
+	/// Converts this element and its descendants into a JSON value
+	///
+	/// An element is represented as a JSON object with the keys `name` (a string),
+	/// `attributes` (an object with string values), `children` (an array of elements) and
+	/// `text` (a string, or `null` if the element has no text).  For example,
+	/// `<a x="1">hi<b/></a>` becomes:
+	///
+	/// ```json
+	/// {"name": "a", "attributes": {"x": "1"}, "children": [{"name": "b", ...}], "text": "hi"}
+	/// ```
+	///
+	/// This is a lossy, but easy to consume, format: namespaces, namespaced attributes,
+	/// comments and processing instructions are left out, and all text of an element is
+	/// joined together.
+	pub fn to_json_value(&self) -> Value {
+		let attributes = self
+			.attributes
+			.iter()
+			.map(|(k, v)| (k.clone(), Value::String(v.clone())))
+			.collect();
+		let children = self.child_elements().map(Element::to_json_value).collect();
+		let text = match self.get_text() {
+			Some(text) => Value::String(text.into_owned()),
+			None => Value::Null,
+		};
+
+		let mut object = Map::new();
+		object.insert("name".to_owned(), Value::String(self.name.clone()));
+		object.insert("attributes".to_owned(), Value::Object(attributes));
+		object.insert("children".to_owned(), Value::Array(children));
+		object.insert("text".to_owned(), text);
+		Value::Object(object)
+	}
+
+	/// Converts a JSON value, as produced by [`to_json_value`](#method.to_json_value), into
+	/// an element
+	///
+	/// Only `name` is required.  The text, if any, is placed before the child elements.
+	pub fn from_json_value(v: &Value) -> Result<Element, JsonConvertError> {
+		let object = v.as_object().ok_or(JsonConvertError::ExpectedObject)?;
+		let name = object
+			.get("name")
+			.and_then(Value::as_str)
+			.ok_or(JsonConvertError::MissingName)?;
+		let mut elem = Element::new(name);
+
+		match object.get("attributes") {
+			None | Some(Value::Null) => {}
+			Some(Value::Object(attributes)) => {
+				for (k, v) in attributes {
+					let v = v
+						.as_str()
+						.ok_or_else(|| JsonConvertError::InvalidField("attributes".to_owned()))?;
+					elem.attributes.insert(k.clone(), v.to_owned());
+				}
+			}
+			Some(_) => return Err(JsonConvertError::InvalidField("attributes".to_owned())),
+		}
+
+		match object.get("text") {
+			None | Some(Value::Null) => {}
+			Some(Value::String(text)) => elem.children.push(XMLNode::Text(text.clone())),
+			Some(_) => return Err(JsonConvertError::InvalidField("text".to_owned())),
+		}
+
+		match object.get("children") {
+			None | Some(Value::Null) => {}
+			Some(Value::Array(children)) => {
+				for child in children {
+					elem.children.push(XMLNode::Element(Element::from_json_value(child)?));
+				}
+			}
+			Some(_) => return Err(JsonConvertError::InvalidField("children".to_owned())),
+		}
+
+		Ok(elem)
+	}
+}
@@ -28,19 +28,53 @@
 //!
 //!
 //! ```
+extern crate indexmap;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+#[cfg(feature = "tokio")]
+extern crate tokio;
 extern crate xml;
 
+pub mod borrowed;
+pub mod diff;
+#[cfg(feature = "serde_json")]
+mod json;
+pub mod merge;
+mod select;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "tokio")]
+mod tokio_impl;
+pub mod util;
+
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
 use std::fmt;
-use std::io::{Read, Write};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::ops::{Index, IndexMut};
+use std::path::Path;
+use std::str::FromStr;
 
+pub use indexmap::IndexMap;
+pub use select::SelectError;
+#[cfg(feature = "serde_json")]
+pub use json::JsonConvertError;
+#[cfg(feature = "tokio")]
+pub use tokio_impl::ParseAsync;
 pub use xml::namespace::Namespace;
+use xml::attribute::OwnedAttribute;
+use xml::name::OwnedName;
 use xml::reader::{EventReader, XmlEvent};
 pub use xml::writer::{EmitterConfig, Error};
 
 /// Represents an XML element.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct Element {
 	/// This elements prefix, if any
 	pub prefix: Option<String>,
@@ -50,20 +84,288 @@ pub struct Element {
 
 	/// The full list of namespaces, if any
 	///
+	/// When parsed, this is the complete set of namespace mappings in scope at this element,
+	/// including those inherited from its ancestors, not just the ones declared on the
+	/// element itself.
+	///
 	/// The `Namespace` type is exported from the `xml-rs` crate.
 	pub namespaces: Option<Namespace>,
 
+	/// The namespace mappings declared on this element itself, if any
+	///
+	/// This is set by the parser and records which of the mappings in `namespaces` were
+	/// newly declared (or overridden) on this element's start tag.  It is not used when
+	/// writing, and is ignored when comparing elements with `==`.
+	pub declared_namespaces: Option<Namespace>,
+
 	/// The name of the Element.  Does not include any namespace info
 	pub name: String,
 
-	/// The Element attributes
-	pub attributes: HashMap<String, String>,
+	/// The Element attributes that are not in a namespace, keyed by name
+	///
+	/// Attributes are kept in the order they were inserted (or parsed), and are written
+	/// out in that same order.  The order is not significant when comparing elements with
+	/// `==`.  The `IndexMap` type is exported from the `indexmap` crate.
+	pub attributes: IndexMap<String, String>,
+
+	/// The Element attributes that are in a namespace (such as `xlink:href`), keyed by
+	/// their qualified name
+	///
+	/// These are written out after the attributes in `attributes`.
+	pub attributes_ns: IndexMap<QName, String>,
 
 	/// Children
-	pub children: Vec<Element>,
+	///
+	/// This includes child elements as well as any text, CDATA, comments and processing
+	/// instructions, in document order.
+	pub children: Vec<XMLNode>,
+}
+
+/// A qualified name, consisting of a local name with an optional namespace and prefix
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct QName {
+	/// The prefix, if any
+	pub prefix: Option<String>,
+
+	/// The namespace URI, if any
+	pub namespace: Option<String>,
+
+	/// The local name.  Does not include any namespace info
+	pub local_name: String,
+}
+
+impl QName {
+	/// Create a new qualified name with the given local name and no namespace
+	pub fn new(local_name: &str) -> QName {
+		QName {
+			prefix: None,
+			namespace: None,
+			local_name: String::from(local_name),
+		}
+	}
+
+	/// Create a new qualified name with the given local name, prefix and namespace
+	pub fn new_ns(local_name: &str, prefix: &str, namespace: &str) -> QName {
+		QName {
+			prefix: Some(String::from(prefix)),
+			namespace: Some(String::from(namespace)),
+			local_name: String::from(local_name),
+		}
+	}
+}
+
+impl fmt::Display for QName {
+	/// Formats the name as `prefix:local_name`, or just `local_name` if there is no prefix
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.prefix {
+			Some(ref prefix) => write!(f, "{}:{}", prefix, self.local_name),
+			None => f.write_str(&self.local_name),
+		}
+	}
+}
+
+/// The default indentation used for pretty-printing, two spaces
+pub const DEFAULT_INDENT: &str = "  ";
+
+/// A node in an XML tree
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[allow(clippy::large_enum_variant)]
+pub enum XMLNode {
+	/// A child element
+	Element(Element),
+	/// Character data
+	Text(String),
+	/// A CDATA section
+	///
+	/// CDATA sections are written back out as CDATA sections, so their content is not
+	/// escaped.
+	CData(String),
+	/// A comment
+	Comment(String),
+	/// A processing instruction, with its target name and optional data
+	ProcessingInstruction(String, Option<String>),
+}
+
+impl XMLNode {
+	/// Returns a reference to the element if this node is an element
+	pub fn as_element(&self) -> Option<&Element> {
+		match *self {
+			XMLNode::Element(ref e) => Some(e),
+			_ => None,
+		}
+	}
+
+	/// Returns a mutable reference to the element if this node is an element
+	pub fn as_mut_element(&mut self) -> Option<&mut Element> {
+		match *self {
+			XMLNode::Element(ref mut e) => Some(e),
+			_ => None,
+		}
+	}
+
+	/// Consumes the node, returning the element if this node is an element
+	pub fn into_element(self) -> Option<Element> {
+		match self {
+			XMLNode::Element(e) => Some(e),
+			_ => None,
+		}
+	}
+
+	/// Returns the text if this node is a text node
+	pub fn as_text(&self) -> Option<&str> {
+		match *self {
+			XMLNode::Text(ref s) => Some(s),
+			_ => None,
+		}
+	}
+
+	/// Returns the text if this node is a CDATA section
+	pub fn as_cdata(&self) -> Option<&str> {
+		match *self {
+			XMLNode::CData(ref s) => Some(s),
+			_ => None,
+		}
+	}
+
+	/// Returns the text if this node is a comment
+	pub fn as_comment(&self) -> Option<&str> {
+		match *self {
+			XMLNode::Comment(ref s) => Some(s),
+			_ => None,
+		}
+	}
+
+	/// Returns the target and data if this node is a processing instruction
+	pub fn as_processing_instruction(&self) -> Option<(&str, Option<&str>)> {
+		match *self {
+			XMLNode::ProcessingInstruction(ref name, ref data) => Some((name, data.as_deref())),
+			_ => None,
+		}
+	}
+
+	fn is_element_named<K>(&self, k: &K) -> bool
+	where
+		String: PartialEq<K>,
+	{
+		match *self {
+			XMLNode::Element(ref e) => e.name == *k,
+			_ => false,
+		}
+	}
+
+	/// `default_ns` is the default namespace in scope in the output, if any
+	fn _write<B: Write>(&self, emitter: &mut xml::writer::EventWriter<B>, default_ns: Option<&str>) -> Result<(), Error> {
+		self._events(&mut |event| emitter.write(event), default_ns)
+	}
+
+	/// Passes the events that make up this node to `sink`, in document order
+	fn _events<'a, E, F: FnMut(xml::writer::events::XmlEvent<'a>) -> Result<(), E>>(
+		&'a self,
+		sink: &mut F,
+		default_ns: Option<&str>,
+	) -> Result<(), E> {
+		use xml::writer::events::XmlEvent;
+
+		match *self {
+			XMLNode::Element(ref e) => e._events(sink, default_ns),
+			XMLNode::Text(ref s) => sink(XmlEvent::Characters(s)),
+			XMLNode::CData(ref s) => sink(XmlEvent::CData(s)),
+			XMLNode::Comment(ref s) => sink(XmlEvent::Comment(s)),
+			XMLNode::ProcessingInstruction(ref name, ref data) => sink(XmlEvent::ProcessingInstruction {
+				name,
+				data: data.as_deref(),
+			}),
+		}
+	}
+}
+
+impl Default for Element {
+	/// Creates an element with an empty name; all other fields are empty too
+	fn default() -> Element {
+		Element::new("")
+	}
+}
+
+impl PartialEq for Element {
+	fn eq(&self, other: &Element) -> bool {
+		self.prefix == other.prefix
+			&& self.namespace == other.namespace
+			&& self.namespaces == other.namespaces
+			&& self.name == other.name
+			&& self.attributes == other.attributes
+			&& self.attributes_ns == other.attributes_ns
+			&& self.children == other.children
+	}
+}
+
+impl Eq for Element {}
+
+impl Hash for Element {
+	/// Hashes all parts of the element.  Attributes are hashed in sorted order, since their
+	/// order is not significant for equality.
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.prefix.hash(state);
+		self.namespace.hash(state);
+		self.namespaces.as_ref().map(|ns| &ns.0).hash(state);
+		self.name.hash(state);
+
+		let mut attributes: Vec<(&String, &String)> = self.attributes.iter().collect();
+		attributes.sort();
+		attributes.hash(state);
+
+		let mut attributes_ns: Vec<(&QName, &String)> = self.attributes_ns.iter().collect();
+		attributes_ns.sort();
+		attributes_ns.hash(state);
+
+		self.children.hash(state);
+	}
+}
+
+impl PartialOrd for Element {
+	fn partial_cmp(&self, other: &Element) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for Element {
+	/// Compares the name, namespace, prefix, namespace mappings, attributes (as sorted
+	/// name/value pairs, first those without and then those with a namespace) and finally
+	/// the children of each element, in that order.
+	///
+	/// This provides a total order for use in sorted collections; it is not intended to have
+	/// any particular meaning in terms of XML.
+	fn cmp(&self, other: &Element) -> Ordering {
+		fn sorted_attributes(e: &Element) -> Vec<(&String, &String)> {
+			let mut attributes: Vec<(&String, &String)> = e.attributes.iter().collect();
+			attributes.sort();
+			attributes
+		}
+
+		fn sorted_attributes_ns(e: &Element) -> Vec<(&QName, &String)> {
+			let mut attributes: Vec<(&QName, &String)> = e.attributes_ns.iter().collect();
+			attributes.sort();
+			attributes
+		}
+
+		self.name
+			.cmp(&other.name)
+			.then_with(|| self.namespace.cmp(&other.namespace))
+			.then_with(|| self.prefix.cmp(&other.prefix))
+			.then_with(|| {
+				let a = self.namespaces.as_ref().map(|ns| &ns.0);
+				let b = other.namespaces.as_ref().map(|ns| &ns.0);
+				a.cmp(&b)
+			})
+			.then_with(|| sorted_attributes(self).cmp(&sorted_attributes(other)))
+			.then_with(|| sorted_attributes_ns(self).cmp(&sorted_attributes_ns(other)))
+			.then_with(|| self.children.cmp(&other.children))
+	}
+}
 
-	/// The text data for this element
-	pub text: Option<String>,
+impl From<Element> for XMLNode {
+	fn from(elem: Element) -> XMLNode {
+		XMLNode::Element(elem)
+	}
 }
 
 /// Errors that can occur parsing XML
@@ -74,6 +376,26 @@ pub enum ParseError {
 	/// This library is unable to process this XML. This can occur if, for
 	/// example, the XML contains processing instructions.
 	CannotParse,
+	/// The XML is nested more deeply than allowed by `ParserConfig::max_depth`
+	MaxDepthExceeded,
+}
+
+impl ParseError {
+	/// Returns the position in the input at which the error occurred, as a 1-based
+	/// `(line, column)` pair, if known.
+	///
+	/// The position is only available for `MalformedXml` errors.
+	pub fn position(&self) -> Option<(u64, u64)> {
+		use xml::common::Position;
+
+		match *self {
+			ParseError::MalformedXml(ref e) => {
+				let pos = e.position();
+				Some((pos.row + 1, pos.column + 1))
+			}
+			ParseError::CannotParse | ParseError::MaxDepthExceeded => None,
+		}
+	}
 }
 
 impl fmt::Display for ParseError {
@@ -81,27 +403,326 @@ impl fmt::Display for ParseError {
 		match *self {
 			ParseError::MalformedXml(ref e) => write!(f, "Malformed XML. {}", e),
 			ParseError::CannotParse => write!(f, "Cannot parse"),
+			ParseError::MaxDepthExceeded => write!(f, "Maximum depth exceeded"),
 		}
 	}
 }
 
 impl std::error::Error for ParseError {
-	fn description(&self) -> &str {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match *self {
+			ParseError::MalformedXml(ref e) => Some(e),
+			ParseError::CannotParse | ParseError::MaxDepthExceeded => None,
+		}
+	}
+}
+
+impl From<xml::reader::Error> for ParseError {
+	fn from(e: xml::reader::Error) -> ParseError {
+		ParseError::MalformedXml(e)
+	}
+}
+
+/// Errors that can occur reading a typed attribute value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttrError<E> {
+	/// The attribute with the given name is not present
+	Missing(String),
+	/// The attribute with the given name could not be parsed into the requested type
+	Invalid(String, E),
+}
+
+impl<E: fmt::Display> fmt::Display for AttrError<E> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match *self {
-			ParseError::MalformedXml(..) => "Malformed XML",
-			ParseError::CannotParse => "Cannot parse",
+			AttrError::Missing(ref name) => write!(f, "Missing attribute \"{}\"", name),
+			AttrError::Invalid(ref name, ref e) => write!(f, "Invalid attribute \"{}\". {}", name, e),
 		}
 	}
+}
 
-	fn cause(&self) -> Option<&std::error::Error> {
+impl<E: std::error::Error + 'static> std::error::Error for AttrError<E> {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
 		match *self {
-			ParseError::MalformedXml(ref e) => Some(e),
-			ParseError::CannotParse => None,
+			AttrError::Missing(..) => None,
+			AttrError::Invalid(_, ref e) => Some(e),
+		}
+	}
+}
+
+/// Configuration options for parsing XML into an `Element`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserConfig {
+	/// Silently skip processing instructions instead of failing with
+	/// `ParseError::CannotParse`.  Defaults to `false`.
+	pub ignore_processing_instructions: bool,
+
+	/// Keep processing instructions as `XMLNode::ProcessingInstruction` nodes.  This takes
+	/// precedence over `ignore_processing_instructions`.  Defaults to `false`.
+	pub preserve_processing_instructions: bool,
+
+	/// Silently skip comments instead of failing with `ParseError::CannotParse`.
+	/// Defaults to `true`.
+	pub ignore_comments: bool,
+
+	/// Keep comments as `XMLNode::Comment` nodes.  This takes precedence over
+	/// `ignore_comments`.  Defaults to `false`.
+	pub preserve_comments: bool,
+
+	/// Trim leading and trailing whitespace from text, discarding text that is
+	/// entirely whitespace.  Defaults to `false`.
+	pub trim_whitespace: bool,
+
+	/// The maximum nesting depth of elements, where the root element is at depth 1.
+	/// Parsing fails with `ParseError::MaxDepthExceeded` if this is exceeded.
+	/// Defaults to `None` (no limit).
+	pub max_depth: Option<usize>,
+
+	/// Configuration for the underlying `xml-rs` reader.
+	///
+	/// Note that the `ignore_comments` option of this configuration is always disabled,
+	/// as comments are handled according to `ParserConfig::ignore_comments` and
+	/// `ParserConfig::preserve_comments` instead.
+	pub reader_config: xml::reader::ParserConfig,
+}
+
+impl ParserConfig {
+	/// Create a new configuration with the default options
+	pub fn new() -> ParserConfig {
+		ParserConfig {
+			ignore_processing_instructions: false,
+			preserve_processing_instructions: false,
+			ignore_comments: true,
+			preserve_comments: false,
+			trim_whitespace: false,
+			max_depth: None,
+			reader_config: xml::reader::ParserConfig::new(),
+		}
+	}
+
+	fn create_reader<R: Read>(&self, r: R) -> EventReader<SkipBom<R>> {
+		let mut reader_config = self.reader_config.clone();
+		reader_config.ignore_comments = false;
+		EventReader::new_with_config(SkipBom::new(r), reader_config)
+	}
+
+	fn check_depth(&self, depth: usize) -> Result<(), ParseError> {
+		match self.max_depth {
+			Some(max) if depth > max => Err(ParseError::MaxDepthExceeded),
+			_ => Ok(()),
+		}
+	}
+}
+
+impl From<xml::reader::ParserConfig> for ParserConfig {
+	/// Wraps an `xml-rs` reader configuration, using the defaults for all other options
+	fn from(reader_config: xml::reader::ParserConfig) -> ParserConfig {
+		ParserConfig {
+			reader_config,
+			..ParserConfig::new()
+		}
+	}
+}
+
+impl Default for ParserConfig {
+	fn default() -> ParserConfig {
+		ParserConfig::new()
+	}
+}
+
+/// Configuration options for writing an `Element` as XML
+///
+/// This wraps an `xml-rs` `EmitterConfig` with builder methods for the most common options.
+/// Anywhere a `WriteConfig` is accepted, an `EmitterConfig` can be used as well.
+///
+/// ```
+/// use xmltree::{Element, WriteConfig};
+///
+/// let e = Element::parse("<list><item>1</item><script>a &lt; b</script></list>".as_bytes()).unwrap();
+/// let mut buf = Vec::new();
+/// e.write_with_config(&mut buf, WriteConfig::new().no_declaration().cdata_sections(&["script"])).unwrap();
+/// assert_eq!(buf, b"<list><item>1</item><script><![CDATA[a < b]]></script></list>");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteConfig {
+	/// The names of elements whose text is written as CDATA sections.  Text containing
+	/// `]]>` is still written as ordinary text.  Defaults to none.
+	pub cdata_elements: Vec<String>,
+
+	/// Configuration for the underlying `xml-rs` writer.  Defaults to that used by
+	/// `Element::write`.
+	pub emitter_config: EmitterConfig,
+}
+
+impl WriteConfig {
+	/// Create a new configuration with the default options
+	pub fn new() -> WriteConfig {
+		WriteConfig {
+			cdata_elements: Vec::new(),
+			emitter_config: EmitterConfig::new().autopad_comments(false),
+		}
+	}
+
+	/// Indent nested elements by the given number of spaces per level
+	pub fn indent(mut self, spaces: u8) -> WriteConfig {
+		self.emitter_config = self
+			.emitter_config
+			.perform_indent(true)
+			.indent_string(" ".repeat(spaces as usize));
+		self
+	}
+
+	/// Do not write the XML declaration (`<?xml ...?>`)
+	pub fn no_declaration(mut self) -> WriteConfig {
+		self.emitter_config = self.emitter_config.write_document_declaration(false);
+		self
+	}
+
+	/// Write the text of elements with the given names as CDATA sections
+	pub fn cdata_sections(mut self, names: &[&str]) -> WriteConfig {
+		self.cdata_elements.extend(names.iter().map(|name| (*name).to_owned()));
+		self
+	}
+}
+
+impl From<EmitterConfig> for WriteConfig {
+	/// Wraps an `xml-rs` writer configuration, using the defaults for all other options
+	fn from(emitter_config: EmitterConfig) -> WriteConfig {
+		WriteConfig {
+			emitter_config,
+			..WriteConfig::new()
+		}
+	}
+}
+
+impl Default for WriteConfig {
+	fn default() -> WriteConfig {
+		WriteConfig::new()
+	}
+}
+
+/// A reader that skips a UTF-8 byte-order mark at the start of the data, which `xml-rs`
+/// would otherwise reject
+struct SkipBom<R> {
+	inner: R,
+	start: [u8; 3],
+	pos: usize,
+	len: usize,
+	checked: bool,
+}
+
+impl<R: Read> SkipBom<R> {
+	fn new(inner: R) -> SkipBom<R> {
+		SkipBom {
+			inner,
+			start: [0; 3],
+			pos: 0,
+			len: 0,
+			checked: false,
+		}
+	}
+}
+
+impl<R: Read> Read for SkipBom<R> {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		if !self.checked {
+			while self.len < self.start.len() {
+				match self.inner.read(&mut self.start[self.len..]) {
+					Ok(0) => break,
+					Ok(n) => self.len += n,
+					Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+					Err(e) => return Err(e),
+				}
+			}
+			self.checked = true;
+			if self.start[..self.len] == b"\xEF\xBB\xBF"[..] {
+				self.len = 0;
+			}
+		}
+
+		if self.pos < self.len {
+			let n = buf.len().min(self.len - self.pos);
+			buf[..n].copy_from_slice(&self.start[self.pos..self.pos + n]);
+			self.pos += n;
+			Ok(n)
+		} else {
+			self.inner.read(buf)
+		}
+	}
+}
+
+/// Returns the mappings in `namespace` that are not inherited from the parent's mappings,
+/// or `None` if there are none
+fn declared_namespaces(namespace: &Namespace, parent: Option<&Namespace>) -> Option<Namespace> {
+	use xml::namespace::{NS_EMPTY_URI, NS_NO_PREFIX, NS_XMLNS_PREFIX, NS_XML_PREFIX};
+
+	let mut declared = Namespace::empty();
+	for (prefix, uri) in namespace {
+		if prefix == NS_XML_PREFIX || prefix == NS_XMLNS_PREFIX {
+			continue;
+		}
+		let inherited = match parent.and_then(|p| p.get(prefix)) {
+			Some(uri) => Some(uri),
+			None if prefix == NS_NO_PREFIX => Some(NS_EMPTY_URI),
+			None => None,
+		};
+		if inherited != Some(uri) {
+			declared.force_put(prefix, uri);
+		}
+	}
+	if declared.is_empty() {
+		None
+	} else {
+		Some(declared)
+	}
+}
+
+/// Creates an empty element from the contents of a `StartElement` event
+///
+/// `parent` holds the namespace mappings in scope at the parent element, if any.
+fn start_element(
+	name: OwnedName,
+	attributes: Vec<OwnedAttribute>,
+	namespace: Namespace,
+	parent: Option<&Namespace>,
+) -> Element {
+	let mut attr_map = IndexMap::new();
+	let mut attr_ns_map = IndexMap::new();
+	for attr in attributes {
+		if attr.name.namespace.is_some() || attr.name.prefix.is_some() {
+			let qname = QName {
+				prefix: attr.name.prefix,
+				namespace: attr.name.namespace,
+				local_name: attr.name.local_name,
+			};
+			attr_ns_map.insert(qname, attr.value);
+		} else {
+			attr_map.insert(attr.name.local_name, attr.value);
 		}
 	}
+
+	Element {
+		prefix: name.prefix,
+		namespace: name.namespace,
+		declared_namespaces: declared_namespaces(&namespace, parent),
+		namespaces: if namespace.is_essentially_empty() {
+			None
+		} else {
+			Some(namespace)
+		},
+		name: name.local_name,
+		attributes: attr_map,
+		attributes_ns: attr_ns_map,
+		children: Vec::new(),
+	}
 }
 
-fn build<B: Read>(reader: &mut EventReader<B>, mut elem: Element) -> Result<Element, ParseError> {
+fn build<B: Read>(
+	reader: &mut EventReader<B>,
+	config: &ParserConfig,
+	depth: usize,
+	mut elem: Element,
+) -> Result<Element, ParseError> {
 	loop {
 		match reader.next() {
 			Ok(XmlEvent::EndElement { ref name }) => {
@@ -116,33 +737,70 @@ fn build<B: Read>(reader: &mut EventReader<B>, mut elem: Element) -> Result<Elem
 				attributes,
 				namespace,
 			}) => {
-				let mut attr_map = HashMap::new();
-				for attr in attributes {
-					attr_map.insert(attr.name.local_name, attr.value);
-				}
+				config.check_depth(depth + 1)?;
 
-				let new_elem = Element {
-					prefix: name.prefix,
-					namespace: name.namespace,
-					namespaces: if namespace.is_essentially_empty() {
-						None
-					} else {
-						Some(namespace)
-					},
-					name: name.local_name,
-					attributes: attr_map,
-					children: Vec::new(),
-					text: None,
-				};
-				elem.children.push(try!(build(reader, new_elem)));
+				let new_elem = start_element(name, attributes, namespace, elem.namespaces.as_ref());
+				let child = build(reader, config, depth + 1, new_elem)?;
+				elem.children.push(XMLNode::Element(child));
 			}
 			Ok(XmlEvent::Characters(s)) => {
-				elem.text = Some(s);
+				if config.trim_whitespace {
+					let trimmed = s.trim();
+					if !trimmed.is_empty() {
+						elem.children.push(XMLNode::Text(trimmed.to_owned()));
+					}
+				} else {
+					elem.children.push(XMLNode::Text(s));
+				}
+			}
+			Ok(XmlEvent::Whitespace(..)) => (),
+			Ok(XmlEvent::ProcessingInstruction { name, data }) if config.preserve_processing_instructions => {
+				elem.children.push(XMLNode::ProcessingInstruction(name, data));
 			}
-			Ok(XmlEvent::Whitespace(..)) | Ok(XmlEvent::Comment(..)) => (),
-			Ok(XmlEvent::CData(s)) => elem.text = Some(s),
+			Ok(XmlEvent::Comment(s)) if config.preserve_comments => elem.children.push(XMLNode::Comment(s)),
+			Ok(XmlEvent::Comment(..)) if config.ignore_comments => (),
+			Ok(XmlEvent::ProcessingInstruction { .. }) if config.ignore_processing_instructions => (),
+			Ok(XmlEvent::CData(s)) => elem.children.push(XMLNode::CData(s)),
 			Ok(XmlEvent::StartDocument { .. })
 			| Ok(XmlEvent::EndDocument)
+			| Ok(XmlEvent::Comment(..))
+			| Ok(XmlEvent::ProcessingInstruction { .. }) => return Err(ParseError::CannotParse),
+			Err(e) => return Err(ParseError::MalformedXml(e)),
+		}
+	}
+}
+
+/// Parses the next root element, collecting any preserved nodes that precede it into
+/// `prolog`.  Returns `None` at the end of the document.
+fn parse_root<B: Read>(
+	reader: &mut EventReader<B>,
+	config: &ParserConfig,
+	prolog: &mut Vec<XMLNode>,
+) -> Result<Option<Element>, ParseError> {
+	loop {
+		match reader.next() {
+			Ok(XmlEvent::StartElement {
+				name,
+				attributes,
+				namespace,
+			}) => {
+				config.check_depth(1)?;
+
+				let root = start_element(name, attributes, namespace, None);
+				return build(reader, config, 1, root).map(Some);
+			}
+			Ok(XmlEvent::Whitespace(..)) | Ok(XmlEvent::StartDocument { .. }) => continue,
+			Ok(XmlEvent::ProcessingInstruction { name, data }) if config.preserve_processing_instructions => {
+				prolog.push(XMLNode::ProcessingInstruction(name, data));
+			}
+			Ok(XmlEvent::Comment(s)) if config.preserve_comments => prolog.push(XMLNode::Comment(s)),
+			Ok(XmlEvent::Comment(..)) if config.ignore_comments => continue,
+			Ok(XmlEvent::ProcessingInstruction { .. }) if config.ignore_processing_instructions => continue,
+			Ok(XmlEvent::EndDocument) => return Ok(None),
+			Ok(XmlEvent::EndElement { .. })
+			| Ok(XmlEvent::Characters(..))
+			| Ok(XmlEvent::CData(..))
+			| Ok(XmlEvent::Comment(..))
 			| Ok(XmlEvent::ProcessingInstruction { .. }) => return Err(ParseError::CannotParse),
 			Err(e) => return Err(ParseError::MalformedXml(e)),
 		}
@@ -159,137 +817,1882 @@ impl Element {
 			prefix: None,
 			namespace: None,
 			namespaces: None,
-			attributes: HashMap::new(),
+			declared_namespaces: None,
+			attributes: IndexMap::new(),
+			attributes_ns: IndexMap::new(),
 			children: Vec::new(),
-			text: None,
 		}
 	}
 
-	/// Parses some data into an Element
-	pub fn parse<R: Read>(r: R) -> Result<Element, ParseError> {
-		let mut reader = EventReader::new(r);
-		loop {
-			match reader.next() {
-				Ok(XmlEvent::StartElement {
-					name,
-					attributes,
-					namespace,
-				}) => {
-					let mut attr_map = HashMap::new();
-					for attr in attributes {
-						attr_map.insert(attr.name.local_name, attr.value);
-					}
+	/// Create a builder for an element with the given name
+	pub fn builder(name: &str) -> ElementBuilder {
+		ElementBuilder::new(name)
+	}
 
-					let root = Element {
-						prefix: name.prefix,
-						namespace: name.namespace,
-						namespaces: if namespace.is_essentially_empty() {
-							None
-						} else {
-							Some(namespace)
-						},
-						name: name.local_name,
-						attributes: attr_map,
-						children: Vec::new(),
-						text: None,
-					};
-					return build(&mut reader, root);
-				}
-				Ok(XmlEvent::Comment(..))
-				| Ok(XmlEvent::Whitespace(..))
-				| Ok(XmlEvent::StartDocument { .. }) => continue,
-				Ok(XmlEvent::EndDocument)
-				| Ok(XmlEvent::EndElement { .. })
-				| Ok(XmlEvent::Characters(..))
-				| Ok(XmlEvent::CData(..))
-				| Ok(XmlEvent::ProcessingInstruction { .. }) => return Err(ParseError::CannotParse),
-				Err(e) => return Err(ParseError::MalformedXml(e)),
-			}
+	/// Returns a wrapper whose `Debug` output is this element as XML, rather than its
+	/// fields, e.g. `println!("{:?}", elem.debug_xml())`.
+	pub fn debug_xml(&self) -> DebugXml<'_> {
+		DebugXml(self)
+	}
+
+	/// Returns a copy of this element without any of its child nodes
+	///
+	/// The name, namespace information and attributes are copied.  As text is stored in
+	/// child nodes, it is not copied either.
+	pub fn clone_without_children(&self) -> Element {
+		Element {
+			prefix: self.prefix.clone(),
+			namespace: self.namespace.clone(),
+			namespaces: self.namespaces.clone(),
+			declared_namespaces: self.declared_namespaces.clone(),
+			name: self.name.clone(),
+			attributes: self.attributes.clone(),
+			attributes_ns: self.attributes_ns.clone(),
+			children: Vec::new(),
 		}
 	}
 
-	fn _write<B: Write>(&self, emitter: &mut xml::writer::EventWriter<B>) -> Result<(), Error> {
-		use xml::attribute::Attribute;
-		use xml::name::Name;
-		use xml::namespace::Namespace;
-		use xml::writer::events::XmlEvent;
+	/// Returns a new element with the given name that contains this element as its only
+	/// child, e.g. turning `<item/>` into `<root><item/></root>`.
+	pub fn wrap_in(self, parent_name: &str) -> Element {
+		let mut parent = Element::new(parent_name);
+		parent.children.push(XMLNode::Element(self));
+		parent
+	}
 
-		let mut name = Name::local(&self.name);
-		if let Some(ref ns) = self.namespace {
-			name.namespace = Some(ns);
-		}
-		if let Some(ref p) = self.prefix {
-			name.prefix = Some(p);
+	/// Sets the name of this element, returning it so that further calls can be chained.
+	///
+	/// The prefix and namespace are left unchanged.
+	pub fn rename<S: Into<String>>(&mut self, name: S) -> &mut Element {
+		self.name = name.into();
+		self
+	}
+
+	/// Returns this element with its name set to `name`.
+	///
+	/// The prefix and namespace are left unchanged.
+	pub fn with_name<S: Into<String>>(mut self, name: S) -> Element {
+		self.name = name.into();
+		self
+	}
+
+	/// Returns the qualified name of this element, i.e. `prefix:name` if the element has a
+	/// prefix, otherwise just `name`.
+	pub fn qualified_name(&self) -> Cow<'_, str> {
+		match self.prefix {
+			Some(ref prefix) => Cow::Owned(format!("{}:{}", prefix, self.name)),
+			None => Cow::Borrowed(&self.name),
 		}
+	}
 
-		let mut attributes = Vec::with_capacity(self.attributes.len());
-		for (k, v) in &self.attributes {
+	/// Returns the namespace mappings declared on this element itself, keyed by prefix
+	///
+	/// Unlike `namespaces`, this does not include mappings inherited from ancestors.  The
+	/// default namespace has an empty prefix; an empty URI means that `xmlns=""` undeclared
+	/// it.  See also the `declared_namespaces` field.
+	pub fn namespace_declarations(&self) -> HashMap<&str, &str> {
+		self.declared_namespaces
+			.iter()
+			.flat_map(|ns| ns.into_iter())
+			.collect()
+	}
+
+	/// Returns the namespace URI bound to the given prefix, if any.
+	///
+	/// Only `namespaces` is searched, which for a parsed element already contains every
+	/// mapping in scope.  Use an empty prefix to look up the default namespace.
+	pub fn namespace_for_prefix<'a>(&'a self, prefix: &str) -> Option<&'a str> {
+		self.namespaces.as_ref().and_then(|ns| ns.get(prefix))
+	}
+
+	/// Parses some data into an Element
+	///
+	/// The XML declaration (`<?xml ...?>`) is optional, so a bare element such as `<foo/>`
+	/// can be parsed as well.  Parsing stops at the end of the root element.  A UTF-8
+	/// byte-order mark at the start of the data is skipped.
+	///
+	/// Parsing is synchronous.  With the `tokio` feature, `parse_async` parses data from
+	/// an async reader.
+	pub fn parse<R: Read>(r: R) -> Result<Element, ParseError> {
+		Element::parse_with_config(r, &ParserConfig::new())
+	}
+
+	/// Opens the file at the given path and parses it into an Element
+	///
+	/// A failure to open the file is reported as a `MalformedXml` error.
+	pub fn from_path<P: AsRef<Path>>(p: P) -> Result<Element, ParseError> {
+		let file = File::open(p).map_err(xml::reader::Error::from)?;
+		Element::parse(BufReader::new(file))
+	}
+
+	/// Parses an XML fragment into an Element
+	///
+	/// A fragment is a single element without an XML declaration, such as `<foo a="1"/>`.
+	/// Any content after the end of the element is not read.  This behaves the same as
+	/// [`parse`](#method.parse), and exists to make the intent explicit.
+	pub fn parse_fragment<R: Read>(r: R) -> Result<Element, ParseError> {
+		Element::parse(r)
+	}
+
+	/// Parses some data into an Element, silently skipping processing instructions and
+	/// comments instead of failing with `ParseError::CannotParse`
+	///
+	/// This is a shorthand for [`parse_with_config`](#method.parse_with_config) with
+	/// `ignore_processing_instructions` and `ignore_comments` set, which makes it suitable for
+	/// documents (such as those from office formats) that contain processing instructions.
+	pub fn parse_lenient<R: Read>(r: R) -> Result<Element, ParseError> {
+		let config = ParserConfig {
+			ignore_processing_instructions: true,
+			ignore_comments: true,
+			..ParserConfig::new()
+		};
+		Element::parse_with_config(r, &config)
+	}
+
+	/// Parses some data into an Element using the provided configuration
+	pub fn parse_with_config<R: Read>(r: R, config: &ParserConfig) -> Result<Element, ParseError> {
+		Element::parse_with_prolog(r, config).map(|(_, root)| root)
+	}
+
+	/// Parses some data into an Element, using the provided configuration for the
+	/// underlying `xml-rs` reader
+	///
+	/// All other options are set to their defaults, see [`ParserConfig`](struct.ParserConfig.html).
+	pub fn parse_with_reader_config<R: Read>(r: R, config: xml::reader::ParserConfig) -> Result<Element, ParseError> {
+		Element::parse_with_config(r, &ParserConfig::from(config))
+	}
+
+	/// Parses some data into an Element using the provided configuration, also returning
+	/// any nodes that appear before the root element.
+	///
+	/// Only processing instructions and comments that are preserved according to `config`
+	/// are returned as part of the prolog.  The prolog can be written back out using
+	/// [`write_with_prolog`](#method.write_with_prolog).
+	pub fn parse_with_prolog<R: Read>(r: R, config: &ParserConfig) -> Result<(Vec<XMLNode>, Element), ParseError> {
+		let mut reader = config.create_reader(r);
+		let mut prolog = Vec::new();
+		match parse_root(&mut reader, config, &mut prolog)? {
+			Some(root) => Ok((prolog, root)),
+			None => Err(ParseError::CannotParse),
+		}
+	}
+
+	/// Parses all root elements from a stream of XML data
+	///
+	/// Some XML streams, such as log files or XMPP sessions, consist of a sequence of
+	/// elements rather than a single root element.  This reads root elements until the
+	/// end of the stream; see [`parse_iter`](#method.parse_iter) for a lazy alternative.
+	pub fn parse_all<R: Read>(r: R) -> Result<Vec<Element>, ParseError> {
+		Element::parse_iter(r).collect()
+	}
+
+	/// Returns an iterator that parses root elements from a stream of XML data one at a time
+	pub fn parse_iter<R: Read>(r: R) -> ElementIter<R> {
+		ElementIter::new(r)
+	}
+
+	/// `default_ns` is the default namespace in scope in the output, if any
+	fn _write<B: Write>(&self, emitter: &mut xml::writer::EventWriter<B>, default_ns: Option<&str>) -> Result<(), Error> {
+		self._events(&mut |event| emitter.write(event), default_ns)
+	}
+
+	/// Passes the events that make up this element to `sink`, in document order
+	///
+	/// `default_ns` is the default namespace in scope in the output, if any
+	fn _events<'a, E, F: FnMut(xml::writer::events::XmlEvent<'a>) -> Result<(), E>>(
+		&'a self,
+		sink: &mut F,
+		default_ns: Option<&str>,
+	) -> Result<(), E> {
+		use xml::attribute::Attribute;
+		use xml::name::Name;
+		use xml::namespace::{Namespace, NS_NO_PREFIX};
+		use xml::writer::events::XmlEvent;
+
+		let mut name = Name::local(&self.name);
+		if let Some(ref ns) = self.namespace {
+			name.namespace = Some(ns);
+		}
+		if let Some(ref p) = self.prefix {
+			name.prefix = Some(p);
+		}
+
+		let mut attributes = Vec::with_capacity(self.attributes.len() + self.attributes_ns.len());
+		for (k, v) in &self.attributes {
 			attributes.push(Attribute {
 				name: Name::local(k),
 				value: v,
 			});
 		}
+		for (k, v) in &self.attributes_ns {
+			attributes.push(Attribute {
+				name: Name {
+					local_name: &k.local_name,
+					namespace: k.namespace.as_deref(),
+					prefix: k.prefix.as_deref(),
+				},
+				value: v,
+			});
+		}
 
-		let empty_ns = Namespace::empty();
-		let namespace = if let Some(ref ns) = self.namespaces {
+		let mut namespace = if let Some(ref ns) = self.namespaces {
 			Cow::Borrowed(ns)
 		} else {
-			Cow::Borrowed(&empty_ns)
+			Cow::Owned(Namespace::empty())
+		};
+
+		// Make sure the element's own prefix (and those of its attributes) are bound to their
+		// namespaces, so that the output can be parsed again even if the mappings are
+		// missing from `namespaces`.
+		if let Some(ref uri) = self.namespace {
+			let prefix = self.prefix.as_ref().map_or(NS_NO_PREFIX, |p| p.as_str());
+			if namespace.get(prefix) != Some(uri.as_str()) {
+				namespace.to_mut().force_put(prefix, uri.as_str());
+			}
+		}
+		for k in self.attributes_ns.keys() {
+			if let (Some(prefix), Some(uri)) = (k.prefix.as_ref(), k.namespace.as_ref()) {
+				if namespace.get(prefix.as_str()) != Some(uri.as_str()) {
+					namespace.to_mut().force_put(prefix.as_str(), uri.as_str());
+				}
+			}
+		}
+
+		// An element without a namespace must not pick up the default namespace of its
+		// parent.  The emitter never writes `xmlns=""`, so it is added as an attribute.
+		if self.namespace.is_none() && self.prefix.is_none() {
+			let inherited = default_ns.is_some_and(|uri| !uri.is_empty());
+			if inherited || namespace.get(NS_NO_PREFIX).is_some_and(|uri| !uri.is_empty()) {
+				namespace.to_mut().force_put(NS_NO_PREFIX, "");
+			}
+			if inherited {
+				attributes.push(Attribute {
+					name: Name::local("xmlns"),
+					value: "",
+				});
+			}
+		}
+		let children_default_ns = match namespace.get(NS_NO_PREFIX) {
+			Some(uri) => Some(uri.to_owned()),
+			None => default_ns.map(str::to_owned),
 		};
 
-		emitter.write(XmlEvent::StartElement {
-			name: name,
+		sink(XmlEvent::StartElement {
+			name,
 			attributes: Cow::Owned(attributes),
-			namespace: namespace,
+			namespace,
 		})?;
-		if let Some(ref t) = self.text {
-			emitter.write(XmlEvent::Characters(t))?;
+		for node in &self.children {
+			node._events(sink, children_default_ns.as_deref())?;
+		}
+		sink(XmlEvent::EndElement { name: Some(name) })
+	}
+
+	/// Returns the events that the writer produces for this element, from its start to its
+	/// end element
+	///
+	/// No `StartDocument` event is included.  The events borrow from this element, and can
+	/// be passed to an `xml-rs` `EventWriter` or processed in any other way.
+	pub fn to_events(&self) -> Vec<xml::writer::events::XmlEvent<'_>> {
+		let mut events = Vec::new();
+		self._events(
+			&mut |event| {
+				events.push(event);
+				Ok(())
+			},
+			None,
+		)
+		.unwrap_or_else(|e: std::convert::Infallible| match e {});
+		events
+	}
+
+	/// Returns the maximum nesting depth of elements in this subtree, where this element is
+	/// at depth 1.
+	pub fn max_depth(&self) -> usize {
+		1 + self.child_elements().map(Element::max_depth).max().unwrap_or(0)
+	}
+
+	/// Returns the total number of elements in this subtree, including this element.
+	pub fn node_count(&self) -> usize {
+		1 + self.descendants().count()
+	}
+
+	/// Returns a rough estimate of the number of bytes this element takes up when written
+	/// out with [`write`](#method.write).
+	///
+	/// The estimate is an upper bound for compact (non-indented) output: it assumes that
+	/// every character that may need escaping is escaped, and that all namespace
+	/// declarations are written out.
+	pub fn xml_size_estimate(&self) -> usize {
+		// `<?xml version="1.0" encoding="utf-8"?>`
+		const DECLARATION: usize = 38;
+		DECLARATION + self._xml_size_estimate()
+	}
+
+	fn _xml_size_estimate(&self) -> usize {
+		// In attribute values, line breaks are also escaped, as `&#xA;` and `&#xD;`
+		fn escaped_len(s: &str, attribute: bool) -> usize {
+			s.chars()
+				.map(|c| match c {
+					'<' | '>' => 4,
+					'&' => 5,
+					'"' | '\'' => 6,
+					'\n' | '\r' if attribute => 5,
+					c => c.len_utf8(),
+				})
+				.sum()
+		}
+
+		let qname_len = self.name.len() + self.prefix.as_ref().map_or(0, |p| p.len() + 1);
+		// `<qname>` and `</qname>`
+		let mut size = 2 * qname_len + 5;
+
+		for (k, v) in &self.attributes {
+			// ` k="v"`
+			size += k.len() + escaped_len(v, true) + 4;
+		}
+		for (k, v) in &self.attributes_ns {
+			// ` prefix:k="v"`, plus a possible ` xmlns:prefix="uri"`
+			let prefix_len = k.prefix.as_ref().map_or(0, |p| p.len());
+			let uri_len = k.namespace.as_ref().map_or(0, |ns| escaped_len(ns, true));
+			size += 2 * prefix_len + k.local_name.len() + escaped_len(v, true) + uri_len + 15;
+		}
+		if let Some(ref ns) = self.namespaces {
+			for (prefix, uri) in &ns.0 {
+				// ` xmlns:prefix="uri"`
+				size += prefix.len() + escaped_len(uri, true) + 10;
+			}
 		}
-		for elem in &self.children {
-			elem._write(emitter)?;
+		if let Some(ref uri) = self.namespace {
+			// a declaration for the element's own prefix may be added
+			size += self.prefix.as_ref().map_or(0, |p| p.len()) + escaped_len(uri, true) + 10;
+		} else if self.prefix.is_none() {
+			// ` xmlns=""` may be added to undeclare an inherited default namespace
+			size += 9;
 		}
-		emitter.write(XmlEvent::EndElement { name: Some(name) })?;
 
-		Ok(())
+		for node in &self.children {
+			size += match *node {
+				XMLNode::Element(ref e) => e._xml_size_estimate(),
+				XMLNode::Text(ref t) => escaped_len(t, false),
+				// `<![CDATA[` and `]]>`
+				XMLNode::CData(ref t) => t.len() + 12,
+				// `<!-- ` and ` -->`
+				XMLNode::Comment(ref t) => t.len() + 9,
+				// `<?name data?>`
+				XMLNode::ProcessingInstruction(ref name, ref data) => {
+					name.len() + data.as_ref().map_or(0, |d| d.len() + 1) + 4
+				}
+			};
+		}
+		size
 	}
 
 	/// Writes out this element as the root element in an new XML document
+	///
+	/// Comments are written exactly as they are stored, without any padding.
 	pub fn write<W: Write>(&self, w: W) -> Result<(), Error> {
-		self.write_with_config(w, EmitterConfig::new())
+		self.write_with_config(w, EmitterConfig::new().autopad_comments(false))
 	}
 
-	/// Writes out this element as the root element in a new XML document using the provided configuration
-	pub fn write_with_config<W: Write>(&self, w: W, config: EmitterConfig) -> Result<(), Error> {
+	/// Creates the file at the given path, replacing any existing file, and writes out this
+	/// element as the root element of a new XML document into it.
+	pub fn write_to_path<P: AsRef<Path>>(&self, p: P) -> Result<(), Error> {
+		let mut w = BufWriter::new(File::create(p)?);
+		self.write(&mut w)?;
+		w.flush()?;
+		Ok(())
+	}
+
+	/// Writes out this element as an XML fragment, i.e. without the XML declaration
+	///
+	/// Comments are written exactly as they are stored, without any padding.
+	pub fn write_fragment<W: Write>(&self, w: W) -> Result<(), Error> {
+		let config = EmitterConfig::new()
+			.autopad_comments(false)
+			.write_document_declaration(false);
+		self.write_with_config(w, config)
+	}
+
+	/// Writes out this element as the root element in a new XML document, indenting nested
+	/// elements with the given string (such as [`DEFAULT_INDENT`](constant.DEFAULT_INDENT.html))
+	///
+	/// Elements that contain text are not indented inside, so the text itself is unchanged.
+	pub fn pretty_print<W: Write>(&self, w: W, indent: &str) -> Result<(), Error> {
+		let config = EmitterConfig::new()
+			.autopad_comments(false)
+			.perform_indent(true)
+			.indent_string(indent.to_owned());
+		self.write_with_config(w, config)
+	}
+
+	/// Writes out this element as an indented XML document and returns it as a `String`
+	///
+	/// See [`pretty_print`](#method.pretty_print) for details.
+	///
+	/// # Panics
+	///
+	/// Panics if the element cannot be written, see [`write_to_string`](#method.write_to_string).
+	pub fn to_pretty_string(&self, indent: &str) -> String {
+		let mut buf = Vec::new();
+		self.pretty_print(&mut buf, indent).expect("failed to write element");
+		String::from_utf8(buf).expect("failed to write element")
+	}
+
+	/// Writes out this element as compactly as possible and returns it as a `String`
+	///
+	/// No indentation or line breaks are added, and the XML declaration is left out, so
+	/// the output is the same as that of [`write_fragment`](#method.write_fragment).
+	///
+	/// # Panics
+	///
+	/// Panics if the element cannot be written, see [`write_to_string`](#method.write_to_string).
+	pub fn to_compact_string(&self) -> String {
+		let config = EmitterConfig::new()
+			.autopad_comments(false)
+			.perform_indent(false)
+			.write_document_declaration(false)
+			.normalize_empty_elements(true);
+		let mut buf = Vec::new();
+		self.write_with_config(&mut buf, config).expect("failed to write element");
+		String::from_utf8(buf).expect("failed to write element")
+	}
+
+	/// Writes out the child nodes of this element one after another, without the element
+	/// itself and without an XML declaration
+	///
+	/// This is useful when the element is only a container for the content to be written.
+	/// Comments are written exactly as they are stored, without any padding.
+	pub fn write_children_only<W: Write>(&self, w: W) -> Result<(), Error> {
 		use xml::writer::EventWriter;
 
+		let config = EmitterConfig::new()
+			.autopad_comments(false)
+			.write_document_declaration(false);
 		let mut emitter = EventWriter::new_with_config(w, config);
-		self._write(&mut emitter)
+		for node in &self.children {
+			node._write(&mut emitter, None)?;
+		}
+		Ok(())
 	}
 
-	/// Find a child element with the given name and return a reference to it.
-	pub fn get_child<K>(&self, k: K) -> Option<&Element>
-	where
-		String: PartialEq<K>,
-	{
-		self.children.iter().find(|e| e.name == k)
+	/// Writes out this element as the root element in a new XML document using the provided configuration
+	///
+	/// The configuration can be a `WriteConfig` or an `EmitterConfig`.
+	pub fn write_with_config<W: Write, C: Into<WriteConfig>>(&self, w: W, config: C) -> Result<(), Error> {
+		self.write_with_prolog(&[], w, config)
 	}
 
-	/// Find a child element with the given name and return a mutable reference to it.
-	pub fn get_mut_child<K>(&mut self, k: K) -> Option<&mut Element>
-	where
-		String: PartialEq<K>,
-	{
-		self.children.iter_mut().find(|e| e.name == k)
+	/// Writes out this element as the root element in a new XML document and returns it as a `String`
+	pub fn write_to_string(&self) -> Result<String, Error> {
+		let mut buf = Vec::new();
+		self.write(&mut buf)?;
+		String::from_utf8(buf).map_err(|e| Error::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
 	}
 
-	/// Find a child element with the given name, remove and return it.
-	pub fn take_child<K>(&mut self, k: K) -> Option<Element>
-	where
-		String: PartialEq<K>,
-	{
-		self.children
+	/// Writes out this element as the root element in a new XML document and returns it as a `String`
+	///
+	/// # Panics
+	///
+	/// Panics if the element cannot be written, see [`write_to_string`](#method.write_to_string).
+	pub fn to_xml_string(&self) -> String {
+		self.write_to_string().expect("failed to write element")
+	}
+
+	/// Writes out this element as the root element in a new XML document, preceded by the
+	/// given prolog nodes (such as processing instructions), using the provided configuration
+	///
+	/// The configuration can be a `WriteConfig` or an `EmitterConfig`.
+	pub fn write_with_prolog<W: Write, C: Into<WriteConfig>>(&self, prolog: &[XMLNode], w: W, config: C) -> Result<(), Error> {
+		use xml::writer::EventWriter;
+
+		let WriteConfig {
+			cdata_elements,
+			emitter_config,
+		} = config.into();
+		let mut emitter = EventWriter::new_with_config(w, emitter_config);
+		for node in prolog {
+			node._write(&mut emitter, None)?;
+		}
+		if cdata_elements.is_empty() {
+			self._write(&mut emitter, None)
+		} else {
+			let mut elem = self.clone();
+			elem._text_to_cdata(&cdata_elements);
+			elem.for_each_descendant_mut(|e| e._text_to_cdata(&cdata_elements));
+			elem._write(&mut emitter, None)
+		}
+	}
+
+	/// Turns the text children of this element into CDATA sections if its name is in `names`
+	fn _text_to_cdata(&mut self, names: &[String]) {
+		if !names.contains(&self.name) {
+			return;
+		}
+		for node in &mut self.children {
+			if let XMLNode::Text(ref mut text) = *node {
+				if !text.contains("]]>") {
+					*node = XMLNode::CData(std::mem::take(text));
+				}
+			}
+		}
+	}
+
+	/// Writes out this element in (best-effort) canonical form, as described by
+	/// [Canonical XML 1.0](https://www.w3.org/TR/xml-c14n) without comments
+	///
+	/// In particular:
+	///
+	/// * there is no XML declaration, and comments are left out
+	/// * empty elements are written with an explicit end tag
+	/// * each element declares every namespace in scope at it (from `namespaces`, plus those
+	///   used by its own prefix and attributes) that is not already declared by an ancestor
+	///   in the output, whether or not it is used.  Declarations are sorted by prefix, and
+	///   attributes by namespace URI and then local name
+	/// * CDATA sections are written as (escaped) text
+	///
+	/// The output is byte-for-byte deterministic, which makes it suitable for signing and
+	/// comparing documents.  Whitespace is written as it is stored in the tree.
+	pub fn write_canonical<W: Write>(&self, mut w: W) -> Result<(), Error> {
+		self._write_canonical(&mut w, &BTreeMap::new())?;
+		Ok(())
+	}
+
+	fn _write_canonical<W: Write>(&self, w: &mut W, rendered: &BTreeMap<String, String>) -> std::io::Result<()> {
+		use xml::namespace::{NS_EMPTY_URI, NS_NO_PREFIX, NS_XMLNS_PREFIX, NS_XML_PREFIX};
+
+		fn escape(s: &str, attribute: bool) -> Cow<'_, str> {
+			if !s.contains(['&', '<', '>', '"', '\t', '\n', '\r']) {
+				return Cow::Borrowed(s);
+			}
+			let mut escaped = String::with_capacity(s.len());
+			for c in s.chars() {
+				match c {
+					'&' => escaped.push_str("&amp;"),
+					'<' => escaped.push_str("&lt;"),
+					'>' if !attribute => escaped.push_str("&gt;"),
+					'"' if attribute => escaped.push_str("&quot;"),
+					'\t' if attribute => escaped.push_str("&#x9;"),
+					'\n' if attribute => escaped.push_str("&#xA;"),
+					'\r' => escaped.push_str("&#xD;"),
+					c => escaped.push(c),
+				}
+			}
+			Cow::Owned(escaped)
+		}
+
+		// the namespaces in scope at this element, including those used by the element and
+		// its attributes
+		let mut scope: BTreeMap<String, String> = match self.namespaces {
+			Some(ref ns) => ns.0.clone(),
+			None => BTreeMap::new(),
+		};
+		match self.namespace {
+			Some(ref uri) => {
+				let prefix = self.prefix.clone().unwrap_or_default();
+				scope.insert(prefix, uri.clone());
+			}
+			None if self.prefix.is_none() => {
+				scope.insert(NS_NO_PREFIX.to_owned(), NS_EMPTY_URI.to_owned());
+			}
+			None => {}
+		}
+		for k in self.attributes_ns.keys() {
+			if let (Some(prefix), Some(uri)) = (k.prefix.as_ref(), k.namespace.as_ref()) {
+				scope.insert(prefix.clone(), uri.clone());
+			}
+		}
+		scope.remove(NS_XML_PREFIX);
+		scope.remove(NS_XMLNS_PREFIX);
+
+		write!(w, "<{}", self.qualified_name())?;
+
+		let mut now_rendered = rendered.clone();
+		for (prefix, uri) in scope {
+			// an empty default namespace only needs declaring if it undoes a non-empty one
+			if rendered.get(&prefix).map_or(NS_EMPTY_URI, String::as_str) == uri {
+				continue;
+			}
+			if prefix == NS_NO_PREFIX {
+				write!(w, " xmlns=\"{}\"", escape(&uri, true))?;
+			} else {
+				write!(w, " xmlns:{}=\"{}\"", prefix, escape(&uri, true))?;
+			}
+			now_rendered.insert(prefix, uri);
+		}
+
+		let mut attributes: Vec<(&str, &str, Cow<'_, str>, &str)> = self
+			.attributes
+			.iter()
+			.map(|(k, v)| ("", k.as_str(), Cow::Borrowed(k.as_str()), v.as_str()))
+			.collect();
+		attributes.extend(self.attributes_ns.iter().map(|(k, v)| {
+			let namespace = k.namespace.as_deref().unwrap_or("");
+			(namespace, k.local_name.as_str(), Cow::Owned(k.to_string()), v.as_str())
+		}));
+		attributes.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+		for (_, _, name, value) in attributes {
+			write!(w, " {}=\"{}\"", name, escape(value, true))?;
+		}
+		w.write_all(b">")?;
+
+		for node in &self.children {
+			match node {
+				XMLNode::Element(e) => e._write_canonical(w, &now_rendered)?,
+				XMLNode::Text(s) | XMLNode::CData(s) => w.write_all(escape(s, false).as_bytes())?,
+				XMLNode::Comment(_) => {}
+				XMLNode::ProcessingInstruction(name, Some(data)) if !data.is_empty() => {
+					write!(w, "<?{} {}?>", name, data)?
+				}
+				XMLNode::ProcessingInstruction(name, _) => write!(w, "<?{}?>", name)?,
+			}
+		}
+
+		write!(w, "</{}>", self.qualified_name())
+	}
+
+	/// Returns the value of the attribute with the given name, if any.
+	pub fn get_attribute(&self, name: &str) -> Option<&str> {
+		self.attributes.get(name).map(String::as_str)
+	}
+
+	/// Returns the value of the attribute with the given name, or `default` if there is
+	/// none.
+	pub fn get_attribute_or<'a>(&'a self, name: &str, default: &'a str) -> &'a str {
+		self.get_attribute(name).unwrap_or(default)
+	}
+
+	/// Returns the value of the attribute with the given name, or the result of `f` if
+	/// there is none.
+	pub fn get_attribute_or_else<'a, F: FnOnce() -> &'a str>(&'a self, name: &str, f: F) -> &'a str {
+		self.get_attribute(name).unwrap_or_else(f)
+	}
+
+	/// Sets the value of an attribute, replacing any existing value.
+	pub fn set_attribute<K: Into<String>, V: Into<String>>(&mut self, name: K, value: V) {
+		self.attributes.insert(name.into(), value.into());
+	}
+
+	/// Removes the attribute with the given name, returning its value if it was present.
+	///
+	/// The order of the remaining attributes is preserved.
+	pub fn remove_attribute(&mut self, name: &str) -> Option<String> {
+		self.attributes.shift_remove(name)
+	}
+
+	/// Returns `true` if this element has an attribute with the given name.
+	pub fn has_attribute(&self, name: &str) -> bool {
+		self.attributes.contains_key(name)
+	}
+
+	/// Returns the value of the attribute with the given local name and namespace, if any.
+	///
+	/// The prefix of the attribute is not taken into account.
+	pub fn get_attribute_ns(&self, name: &str, namespace: &str) -> Option<&str> {
+		self.attributes_ns
+			.iter()
+			.find(|(k, _)| k.local_name == name && k.namespace.as_deref() == Some(namespace))
+			.map(|(_, v)| v.as_str())
+	}
+
+	/// Sets the value of a namespaced attribute, replacing any existing value.
+	pub fn set_attribute_ns<V: Into<String>>(&mut self, name: QName, value: V) {
+		self.attributes_ns.insert(name, value.into());
+	}
+
+	/// Removes the attribute with the given local name and namespace, returning its value if it
+	/// was present.
+	pub fn remove_attribute_ns(&mut self, name: &str, namespace: &str) -> Option<String> {
+		let index = self
+			.attributes_ns
+			.keys()
+			.position(|k| k.local_name == name && k.namespace.as_deref() == Some(namespace))?;
+		self.attributes_ns.shift_remove_index(index).map(|(_, v)| v)
+	}
+
+	/// Parses the value of the attribute with the given name into `T`.
+	///
+	/// Returns `None` if the attribute is not present.
+	pub fn get_attr_as<T: FromStr>(&self, name: &str) -> Option<Result<T, T::Err>> {
+		self.get_attribute(name).map(str::parse)
+	}
+
+	/// Parses the value of the attribute with the given name into `T`, failing if the
+	/// attribute is not present.
+	pub fn required_attr_as<T: FromStr>(&self, name: &str) -> Result<T, AttrError<T::Err>> {
+		match self.get_attr_as(name) {
+			Some(Ok(value)) => Ok(value),
+			Some(Err(e)) => Err(AttrError::Invalid(name.to_owned(), e)),
+			None => Err(AttrError::Missing(name.to_owned())),
+		}
+	}
+
+	/// Copies the attributes of `source` into this element, keeping the value of any
+	/// attribute this element already has.
+	///
+	/// Only the attributes that are not in a namespace are copied.
+	pub fn copy_attributes_from(&mut self, source: &Element) {
+		for (k, v) in &source.attributes {
+			if !self.attributes.contains_key(k) {
+				self.attributes.insert(k.clone(), v.clone());
+			}
+		}
+	}
+
+	/// Copies the attributes of `source` into this element, replacing the value of any
+	/// attribute this element already has.
+	///
+	/// Only the attributes that are not in a namespace are copied.
+	pub fn merge_attributes_from(&mut self, source: &Element) {
+		for (k, v) in &source.attributes {
+			self.attributes.insert(k.clone(), v.clone());
+		}
+	}
+
+	/// Returns an iterator over the `(name, value)` pairs of the attributes that are not in
+	/// a namespace, sorted by name.
+	///
+	/// Iterating over `attributes` directly yields them in insertion order instead.
+	pub fn iter_attributes_sorted(&self) -> impl Iterator<Item = (&str, &str)> {
+		let mut attributes: Vec<(&str, &str)> = self
+			.attributes
+			.iter()
+			.map(|(k, v)| (k.as_str(), v.as_str()))
+			.collect();
+		attributes.sort_unstable();
+		attributes.into_iter()
+	}
+
+	/// Returns an iterator over the names of the attributes that are not in a namespace, in
+	/// insertion order.
+	pub fn attribute_names(&self) -> impl Iterator<Item = &str> {
+		self.attributes.keys().map(String::as_str)
+	}
+
+	/// Returns `true` if this element has all of the given attributes.
+	pub fn has_all_attributes(&self, names: &[&str]) -> bool {
+		names.iter().all(|name| self.attributes.contains_key(*name))
+	}
+
+	/// Returns those of the given attribute names that this element does not have, in the
+	/// order they were given.
+	pub fn missing_attributes<'a>(&self, required: &[&'a str]) -> Vec<&'a str> {
+		required
 			.iter()
-			.position(|e| e.name == k)
-			.map(|i| self.children.remove(i))
+			.filter(|name| !self.attributes.contains_key(**name))
+			.cloned()
+			.collect()
+	}
+
+	/// Keeps only the attributes for which `pred(name, value)` returns `true`.
+	///
+	/// Only the attributes that are not in a namespace are visited.  The order of the
+	/// remaining attributes is preserved.
+	pub fn retain_attributes<P: FnMut(&str, &str) -> bool>(&mut self, mut pred: P) {
+		self.attributes.retain(|k, v| pred(k, v));
+	}
+
+	/// Removes all attributes whose name starts with `prefix`, such as `on` for event
+	/// handlers like `onclick`.
+	///
+	/// This is a plain string comparison: namespaced attributes are not affected, even if
+	/// their namespace prefix matches.
+	pub fn remove_attributes_with_prefix(&mut self, prefix: &str) {
+		self.retain_attributes(|k, _| !k.starts_with(prefix));
+	}
+
+	/// Renames the attribute `old` to `new`, keeping its value and position.
+	///
+	/// If an attribute named `new` already exists, it is replaced when `overwrite` is
+	/// `true`; otherwise nothing is changed.  Returns `true` if the attribute was renamed.
+	pub fn rename_attribute<S: Into<String>>(&mut self, old: &str, new: S, overwrite: bool) -> bool {
+		let new = new.into();
+		let mut index = match self.attributes.get_index_of(old) {
+			Some(index) => index,
+			None => return false,
+		};
+		if new == old {
+			return true;
+		}
+		if let Some(existing) = self.attributes.get_index_of(&new) {
+			if !overwrite {
+				return false;
+			}
+			self.attributes.shift_remove_index(existing);
+			if existing < index {
+				index -= 1;
+			}
+		}
+		if let Some((_, value)) = self.attributes.shift_remove_index(index) {
+			self.attributes.shift_insert(index, new, value);
+		}
+		true
+	}
+
+	/// Returns an iterator over the child elements, skipping any other kinds of nodes.
+	pub fn child_elements(&self) -> impl Iterator<Item = &Element> {
+		self.children.iter().filter_map(XMLNode::as_element)
+	}
+
+	/// Returns an iterator over the child elements, skipping any other kinds of nodes.
+	///
+	/// This is the same as [`child_elements`](#method.child_elements). To access all child
+	/// nodes, use the `children` field.
+	pub fn children(&self) -> impl Iterator<Item = &Element> {
+		self.child_elements()
+	}
+
+	/// Returns an iterator over mutable references to the child elements, skipping any
+	/// other kinds of nodes.
+	pub fn children_mut(&mut self) -> impl Iterator<Item = &mut Element> {
+		self.children.iter_mut().filter_map(XMLNode::as_mut_element)
+	}
+
+	/// Returns the concatenation of all text and CDATA child nodes, or `None` if there are
+	/// none.
+	pub fn get_text(&self) -> Option<Cow<'_, str>> {
+		let mut texts = self.children.iter().filter_map(|node| match *node {
+			XMLNode::Text(ref s) | XMLNode::CData(ref s) => Some(s.as_str()),
+			_ => None,
+		});
+
+		let first = texts.next()?;
+		match texts.next() {
+			None => Some(Cow::Borrowed(first)),
+			Some(second) => {
+				let mut text = String::from(first);
+				text.push_str(second);
+				text.extend(texts);
+				Some(Cow::Owned(text))
+			}
+		}
+	}
+
+	/// Returns the concatenation of all text and CDATA nodes in this element and all of its
+	/// descendants, in document order.
+	///
+	/// This is like the DOM `textContent` property.  Unlike [`get_text`](#method.get_text),
+	/// it includes the text of nested elements.
+	pub fn get_text_content(&self) -> String {
+		let mut content = String::new();
+		self._get_text_content(&mut content);
+		content
+	}
+
+	fn _get_text_content(&self, content: &mut String) {
+		for node in &self.children {
+			match node {
+				XMLNode::Element(e) => e._get_text_content(content),
+				XMLNode::Text(s) | XMLNode::CData(s) => content.push_str(s),
+				XMLNode::Comment(_) | XMLNode::ProcessingInstruction(..) => {}
+			}
+		}
+	}
+
+	/// Returns the text content of this element as UTF-8 bytes, see
+	/// [`get_text_content`](#method.get_text_content).
+	pub fn get_text_content_bytes(&self) -> Vec<u8> {
+		self.get_text_content().into_bytes()
+	}
+
+	/// Returns the concatenation of all text and CDATA child nodes, or an empty string if
+	/// there are none.
+	///
+	/// This is a shorthand for `get_text().unwrap_or_default()`.
+	pub fn get_text_or_empty(&self) -> Cow<'_, str> {
+		self.get_text().unwrap_or_default()
+	}
+
+	/// Replaces all text and CDATA child nodes with a single text node.
+	///
+	/// The new node takes the place of the first text or CDATA node that was removed, or is
+	/// appended if there were none.  Child elements and other nodes are left in place.
+	pub fn set_text<S: Into<String>>(&mut self, text: S) {
+		let index = self
+			.children
+			.iter()
+			.position(|node| matches!(node, XMLNode::Text(_) | XMLNode::CData(_)))
+			.unwrap_or(self.children.len());
+		self.clear_text();
+		self.children.insert(index, XMLNode::Text(text.into()));
+	}
+
+	/// Returns `true` if this element has any text or CDATA child nodes.
+	pub fn has_text(&self) -> bool {
+		self.children
+			.iter()
+			.any(|node| matches!(node, XMLNode::Text(_) | XMLNode::CData(_)))
+	}
+
+	/// Returns `true` if this element has any child elements.
+	pub fn has_children(&self) -> bool {
+		self.children.iter().any(|node| node.as_element().is_some())
+	}
+
+	/// Returns `true` if this element has no attributes and no child nodes of any kind.
+	pub fn is_empty(&self) -> bool {
+		self.attributes.is_empty() && self.attributes_ns.is_empty() && self.children.is_empty()
+	}
+
+	/// Removes all text and CDATA child nodes.
+	pub fn clear_text(&mut self) {
+		self.children
+			.retain(|node| !matches!(node, XMLNode::Text(_) | XMLNode::CData(_)));
+	}
+
+	/// Returns an iterator over all descendant elements of this element (not including
+	/// the element itself), in depth-first pre-order.
+	pub fn descendants(&self) -> Descendants<'_> {
+		Descendants {
+			stack: vec![self.children.iter()],
+		}
+	}
+
+	/// Calls a closure on each descendant element of this element (not including the
+	/// element itself), in depth-first pre-order.
+	///
+	/// This is the mutable counterpart to [`descendants`](#method.descendants). An iterator
+	/// cannot be provided here, as it would hand out mutable references to an element and
+	/// to its descendants at the same time.  The closure is called on an element before
+	/// its children are visited, so any changes it makes to the children are visible to
+	/// later calls.
+	pub fn for_each_descendant_mut<F: FnMut(&mut Element)>(&mut self, mut f: F) {
+		self._for_each_descendant_mut(&mut f)
+	}
+
+	fn _for_each_descendant_mut<F: FnMut(&mut Element)>(&mut self, f: &mut F) {
+		for child in self.children_mut() {
+			f(child);
+			child._for_each_descendant_mut(f);
+		}
+	}
+
+	/// Walks this element and all of its descendants with the given visitor, in depth-first
+	/// order.
+	///
+	/// See [`Visitor`](trait.Visitor.html) for the order in which its methods are called.
+	pub fn accept<V: Visitor + ?Sized>(&self, visitor: &mut V) {
+		visitor.enter_element(self);
+		visitor.visit_element(self);
+		for child in self.child_elements() {
+			child.accept(visitor);
+		}
+		visitor.leave_element(self);
+	}
+
+	/// Walks this element and all of its descendants with the given mutable visitor, in
+	/// depth-first order.
+	///
+	/// The children of an element are visited after `enter_element` and `visit_element`
+	/// have been called on it, so any changes made to them there are visible.
+	pub fn accept_mut<V: MutVisitor + ?Sized>(&mut self, visitor: &mut V) {
+		visitor.enter_element(self);
+		visitor.visit_element(self);
+		for child in self.children_mut() {
+			child.accept_mut(visitor);
+		}
+		visitor.leave_element(self);
+	}
+
+	/// Creates a new tree by applying `f` to this element and all of its descendants,
+	/// bottom-up.
+	///
+	/// The children of each element are mapped first, and `f` is then called on a copy of
+	/// the element that contains the mapped children.  Its result replaces the element in
+	/// the new tree.  Nodes other than elements are copied as is.
+	pub fn map_tree<F: Fn(&Element) -> Element>(&self, f: F) -> Element {
+		self._map_tree(&f)
+	}
+
+	fn _map_tree<F: Fn(&Element) -> Element>(&self, f: &F) -> Element {
+		let children = self
+			.children
+			.iter()
+			.map(|node| match node {
+				XMLNode::Element(e) => XMLNode::Element(e._map_tree(f)),
+				node => node.clone(),
+			})
+			.collect();
+		let mut e = self.clone_without_children();
+		e.children = children;
+		f(&e)
+	}
+
+	/// Calls `f` on this element and all of its descendants, bottom-up, modifying the tree
+	/// in place.
+	///
+	/// This is the in-place counterpart to [`map_tree`](#method.map_tree): the closure is
+	/// called on an element after it has been called on all of its children.
+	pub fn in_place_map_tree<F: FnMut(&mut Element)>(&mut self, mut f: F) {
+		self._in_place_map_tree(&mut f)
+	}
+
+	fn _in_place_map_tree<F: FnMut(&mut Element)>(&mut self, f: &mut F) {
+		for child in self.children_mut() {
+			child._in_place_map_tree(f);
+		}
+		f(self);
+	}
+
+	/// Reduces this element and all of its descendants to a single value, visiting them in
+	/// depth-first pre-order and threading the accumulator through each call to `f`.
+	pub fn fold_tree<A, F: Fn(A, &Element) -> A>(&self, init: A, f: F) -> A {
+		let init = f(init, self);
+		self.descendants().fold(init, f)
+	}
+
+	/// Removes all namespace information from this element and all of its descendants.
+	///
+	/// The `prefix`, `namespace`, `namespaces` and `declared_namespaces` fields are set to
+	/// `None`, and any namespaced attributes are moved into `attributes` under their local
+	/// name.  If an attribute with the same local name already exists there, it is kept.
+	pub fn strip_namespaces(&mut self) {
+		self._strip_namespaces();
+		self.for_each_descendant_mut(Element::_strip_namespaces);
+	}
+
+	fn _strip_namespaces(&mut self) {
+		self.prefix = None;
+		self.namespace = None;
+		self.namespaces = None;
+		self.declared_namespaces = None;
+		for (k, v) in std::mem::take(&mut self.attributes_ns) {
+			self.attributes.entry(k.local_name).or_insert(v);
+		}
+	}
+
+	/// Returns a copy of this element with all namespace information removed.
+	///
+	/// See [`strip_namespaces`](#method.strip_namespaces) for details.
+	pub fn without_namespaces(&self) -> Element {
+		let mut e = self.clone();
+		e.strip_namespaces();
+		e
+	}
+
+	/// Moves all namespace declarations of this element and its descendants to this element,
+	/// declaring each namespace in use only once.
+	///
+	/// The `namespaces` and `declared_namespaces` fields of all descendants are set to
+	/// `None`, and those of this element are set to the mappings used by the prefixes (and
+	/// default namespaces) of the elements and namespaced attributes in the tree.  Where
+	/// the same prefix is used for different namespaces, the first one found is declared on
+	/// this element and the others are declared where they are used when writing.
+	pub fn remove_namespace_declarations(&mut self) {
+		use xml::namespace::NS_NO_PREFIX;
+
+		let mut used = Namespace::empty();
+		for e in std::iter::once(&*self).chain(self.descendants()) {
+			if let Some(ref uri) = e.namespace {
+				used.put(e.prefix.as_deref().unwrap_or(NS_NO_PREFIX), uri.as_str());
+			}
+			for k in e.attributes_ns.keys() {
+				if let (Some(prefix), Some(uri)) = (k.prefix.as_ref(), k.namespace.as_ref()) {
+					used.put(prefix.as_str(), uri.as_str());
+				}
+			}
+		}
+
+		self.for_each_descendant_mut(|e| {
+			e.namespaces = None;
+			e.declared_namespaces = None;
+		});
+		if used.is_empty() {
+			self.namespaces = None;
+			self.declared_namespaces = None;
+		} else {
+			self.namespaces = Some(used.clone());
+			self.declared_namespaces = Some(used);
+		}
+	}
+
+	/// Returns a copy of this element with minimal namespace declarations.
+	///
+	/// See [`remove_namespace_declarations`](#method.remove_namespace_declarations) for
+	/// details.
+	pub fn with_minimized_namespaces(&self) -> Element {
+		let mut e = self.clone();
+		e.remove_namespace_declarations();
+		e
+	}
+
+	/// Trims leading and trailing whitespace from the text nodes of this element and all of
+	/// its descendants, and collapses internal runs of whitespace to a single space.
+	///
+	/// Text nodes that end up empty are removed.  CDATA sections are left untouched.
+	pub fn normalize_whitespace(&mut self) {
+		self._normalize_whitespace();
+		self.for_each_descendant_mut(Element::_normalize_whitespace);
+	}
+
+	fn _normalize_whitespace(&mut self) {
+		self.children.retain_mut(|node| match node {
+			XMLNode::Text(text) => {
+				*text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+				!text.is_empty()
+			}
+			_ => true,
+		});
+	}
+
+	/// Returns a copy of this element with whitespace normalized.
+	///
+	/// See [`normalize_whitespace`](#method.normalize_whitespace) for details.
+	pub fn with_normalized_whitespace(&self) -> Element {
+		let mut e = self.clone();
+		e.normalize_whitespace();
+		e
+	}
+
+	/// Find the first descendant element (in depth-first pre-order) matching the given
+	/// predicate and return a reference to it.
+	pub fn find_descendant<P: Fn(&Element) -> bool>(&self, pred: P) -> Option<&Element> {
+		self.descendants().find(|e| pred(e))
+	}
+
+	/// Find the first descendant element (in depth-first pre-order) matching the given
+	/// predicate and return a mutable reference to it.
+	pub fn find_descendant_mut<P: Fn(&Element) -> bool>(&mut self, pred: P) -> Option<&mut Element> {
+		self._find_descendant_mut(&pred)
+	}
+
+	fn _find_descendant_mut<P: Fn(&Element) -> bool>(&mut self, pred: &P) -> Option<&mut Element> {
+		for child in self.children_mut() {
+			if pred(child) {
+				return Some(child);
+			}
+			if let Some(found) = child._find_descendant_mut(pred) {
+				return Some(found);
+			}
+		}
+		None
+	}
+
+	/// Find all descendant elements matching the given predicate, in depth-first pre-order.
+	pub fn find_all_descendants<P: Fn(&Element) -> bool>(&self, pred: P) -> Vec<&Element> {
+		self.descendants().filter(|e| pred(e)).collect()
+	}
+
+	/// Find a child element with the given name and return a reference to it.
+	pub fn get_child<K>(&self, k: K) -> Option<&Element>
+	where
+		String: PartialEq<K>,
+	{
+		self.child_elements().find(|e| e.name == k)
+	}
+
+	/// Returns the number of child elements.
+	///
+	/// Other kinds of child nodes are not counted; use `children.len()` to count those too.
+	pub fn child_count(&self) -> usize {
+		self.child_elements().count()
+	}
+
+	/// Returns the number of child elements with the given name.
+	pub fn child_count_named<K>(&self, k: K) -> usize
+	where
+		String: PartialEq<K>,
+	{
+		self.child_elements().filter(|e| e.name == k).count()
+	}
+
+	/// Returns the `n`th child element (counting from zero), skipping any other kinds of
+	/// nodes.
+	pub fn nth_child(&self, n: usize) -> Option<&Element> {
+		self.child_elements().nth(n)
+	}
+
+	/// Returns a mutable reference to the `n`th child element (counting from zero), skipping
+	/// any other kinds of nodes.
+	pub fn nth_child_mut(&mut self, n: usize) -> Option<&mut Element> {
+		self.children_mut().nth(n)
+	}
+
+	/// Returns the first child element, if any.
+	pub fn first_child(&self) -> Option<&Element> {
+		self.child_elements().next()
+	}
+
+	/// Returns the last child element, if any.
+	pub fn last_child(&self) -> Option<&Element> {
+		self.children.iter().rev().find_map(XMLNode::as_element)
+	}
+
+	/// Find a child element with the given name and return its text, if it has any.
+	///
+	/// See [`get_text`](#method.get_text) for how the text is collected.
+	pub fn get_child_text<K>(&self, k: K) -> Option<Cow<'_, str>>
+	where
+		String: PartialEq<K>,
+	{
+		self.get_child(k).and_then(Element::get_text)
+	}
+
+	/// Find a child element with the given name and return its text, or `default` if there
+	/// is no such child or it has no text.
+	pub fn get_child_text_or<'a, K>(&'a self, k: K, default: &'a str) -> Cow<'a, str>
+	where
+		String: PartialEq<K>,
+	{
+		self.get_child_text(k).unwrap_or(Cow::Borrowed(default))
+	}
+
+	/// Find a child element with the given name and return a mutable reference to it.
+	pub fn get_mut_child<K>(&mut self, k: K) -> Option<&mut Element>
+	where
+		String: PartialEq<K>,
+	{
+		self.children_mut().find(|e| e.name == k)
+	}
+
+	/// Find all child elements with the given name and return an iterator over references to them.
+	pub fn get_all_children<'a, K: 'a>(&'a self, k: K) -> impl Iterator<Item = &'a Element> + 'a
+	where
+		String: PartialEq<K>,
+	{
+		self.child_elements().filter(move |e| e.name == k)
+	}
+
+	/// Find all child elements with the given name and return an iterator over mutable references to them.
+	pub fn get_all_children_mut<'a, K: 'a>(&'a mut self, k: K) -> impl Iterator<Item = &'a mut Element> + 'a
+	where
+		String: PartialEq<K>,
+	{
+		self.children_mut().filter(move |e| e.name == k)
+	}
+
+	/// Find a child element with the given name and return a mutable reference to it,
+	/// appending a new empty child element with that name if there is none.
+	pub fn get_or_create_child(&mut self, name: &str) -> &mut Element {
+		let index = match self.child_position(name) {
+			Some(index) => index,
+			None => {
+				self.children.push(XMLNode::Element(Element::new(name)));
+				self.children.len() - 1
+			}
+		};
+		self.children[index]
+			.as_mut_element()
+			.expect("child at position is an element")
+	}
+
+	/// Find a child element with the given name and namespace and return a reference to it.
+	pub fn get_child_ns<'a>(&'a self, name: &str, ns: &str) -> Option<&'a Element> {
+		self.child_elements()
+			.find(|e| e.name == name && e.namespace.as_deref() == Some(ns))
+	}
+
+	/// Find a child element with the given name and namespace and return a mutable reference to it.
+	pub fn get_mut_child_ns<'a>(&'a mut self, name: &str, ns: &str) -> Option<&'a mut Element> {
+		self.children_mut()
+			.find(|e| e.name == name && e.namespace.as_deref() == Some(ns))
+	}
+
+	/// Find all child elements with the given name and namespace and return an iterator over
+	/// references to them.
+	pub fn get_all_children_ns<'a>(&'a self, name: &'a str, ns: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+		self.child_elements()
+			.filter(move |e| e.name == name && e.namespace.as_deref() == Some(ns))
+	}
+
+	/// Find a child element with the given name, remove and return it.
+	pub fn take_child<K>(&mut self, k: K) -> Option<Element>
+	where
+		String: PartialEq<K>,
+	{
+		self.child_position(k)
+			.and_then(|i| self.children.remove(i).into_element())
+	}
+
+	/// Find a child element with the given name and replace it with `new_elem`, returning
+	/// the old element.
+	///
+	/// The new element takes the old one's position.  If there is no such child, nothing
+	/// is changed and `None` is returned.
+	pub fn replace_child<K>(&mut self, k: K, new_elem: Element) -> Option<Element>
+	where
+		String: PartialEq<K>,
+	{
+		let i = self.child_position(k)?;
+		std::mem::replace(&mut self.children[i], XMLNode::Element(new_elem)).into_element()
+	}
+
+	/// Find a child element with the given name and replace it with its own child nodes.
+	///
+	/// The child nodes (including text) take the position the element occupied.  Returns
+	/// `true` if a matching child was found.
+	pub fn unwrap_child<K>(&mut self, k: K) -> bool
+	where
+		String: PartialEq<K>,
+	{
+		let i = match self.child_position(k) {
+			Some(i) => i,
+			None => return false,
+		};
+		if let XMLNode::Element(e) = self.children.remove(i) {
+			self.children.splice(i..i, e.children);
+		}
+		true
+	}
+
+	/// Remove and return the first child element, if any.
+	///
+	/// This takes time proportional to the number of child nodes, as the remaining ones
+	/// are shifted down.  Other kinds of nodes before the element are kept.
+	pub fn take_first_child(&mut self) -> Option<Element> {
+		let i = self.children.iter().position(|node| node.as_element().is_some())?;
+		self.children.remove(i).into_element()
+	}
+
+	/// Remove and return the last child element, if any.
+	///
+	/// This takes constant time if the element is the last child node.  Other kinds of
+	/// nodes after the element are kept.
+	pub fn take_last_child(&mut self) -> Option<Element> {
+		let i = self.children.iter().rposition(|node| node.as_element().is_some())?;
+		self.children.remove(i).into_element()
+	}
+
+	/// Find all child elements with the given name, remove and return them.
+	///
+	/// The remaining children keep their original relative order.
+	pub fn take_all_children<K>(&mut self, k: K) -> Vec<Element>
+	where
+		String: PartialEq<K>,
+	{
+		let children = std::mem::take(&mut self.children);
+		let (taken, kept): (Vec<XMLNode>, Vec<XMLNode>) =
+			children.into_iter().partition(|node| node.is_element_named(&k));
+		self.children = kept;
+		taken.into_iter().filter_map(XMLNode::into_element).collect()
+	}
+
+	/// Insert a child element at position `index` within `children`, shifting all nodes
+	/// after it to the right.
+	///
+	/// # Panics
+	///
+	/// Panics if `index > children.len()`.
+	pub fn insert_child(&mut self, index: usize, elem: Element) {
+		self.children.insert(index, XMLNode::Element(elem));
+	}
+
+	/// Insert a child element before all other children.
+	pub fn prepend_child(&mut self, elem: Element) {
+		self.insert_child(0, elem);
+	}
+
+	/// Append each element from an iterator as a child element.
+	pub fn extend_children<I: IntoIterator<Item = Element>>(&mut self, iter: I) {
+		self.children.extend(iter.into_iter().map(XMLNode::Element));
+	}
+
+	/// Append a clone of each element in a slice as a child element.
+	pub fn extend_children_from(&mut self, other: &[Element]) {
+		self.extend_children(other.iter().cloned());
+	}
+
+	/// Remove all child nodes, returning them in an iterator.
+	pub fn drain_children(&mut self) -> std::vec::Drain<'_, XMLNode> {
+		self.children.drain(..)
+	}
+
+	/// Remove all child nodes, leaving `children` empty, and return them.
+	pub fn take_children(&mut self) -> Vec<XMLNode> {
+		std::mem::take(&mut self.children)
+	}
+
+	/// Move all child nodes to the end of `target`'s children, leaving `children` empty.
+	pub fn move_children_to(&mut self, target: &mut Element) {
+		target.children.append(&mut self.children);
+	}
+
+	/// Remove and return the child node at position `index` within `children`, shifting all
+	/// nodes after it to the left.
+	///
+	/// # Panics
+	///
+	/// Panics if `index` is out of bounds.
+	pub fn remove_child_at(&mut self, index: usize) -> XMLNode {
+		self.children.remove(index)
+	}
+
+	/// Swap the child nodes at positions `a` and `b` within `children`.
+	///
+	/// # Panics
+	///
+	/// Panics if `a` or `b` are out of bounds.
+	pub fn swap_children(&mut self, a: usize, b: usize) {
+		self.children.swap(a, b);
+	}
+
+	/// Find a child element with the given name and return its position within `children`.
+	pub fn child_position<K>(&self, k: K) -> Option<usize>
+	where
+		String: PartialEq<K>,
+	{
+		self.children.iter().position(|node| node.is_element_named(&k))
+	}
+
+	/// Find a child element with the given name and return its position within `children`.
+	///
+	/// This is the same as [`child_position`](#method.child_position).  The position can be
+	/// passed to methods such as [`insert_child`](#method.insert_child) and
+	/// [`remove_child_at`](#method.remove_child_at).
+	pub fn position_of_child<K>(&self, k: K) -> Option<usize>
+	where
+		String: PartialEq<K>,
+	{
+		self.child_position(k)
+	}
+
+	/// Retain only the child elements for which the predicate returns `true`.
+	///
+	/// Other kinds of child nodes (text, comments, etc.) are always kept.
+	pub fn retain_children<P: FnMut(&Element) -> bool>(&mut self, mut pred: P) {
+		self.children.retain(|node| match *node {
+			XMLNode::Element(ref e) => pred(e),
+			_ => true,
+		});
+	}
+
+	/// Return references to the child elements for which the predicate returns `true`.
+	pub fn filter_children<P: FnMut(&Element) -> bool>(&self, mut pred: P) -> Vec<&Element> {
+		self.child_elements().filter(|e| pred(e)).collect()
+	}
+
+	/// Sort the child elements with a comparator function.
+	///
+	/// The sort is stable.  Child elements are reordered among the positions occupied by
+	/// elements; other kinds of child nodes (text, comments, etc.) stay where they are.
+	pub fn sort_children_by<F: FnMut(&Element, &Element) -> Ordering>(&mut self, f: F) {
+		let mut elements: Vec<Element> = self
+			.children_mut()
+			.map(std::mem::take)
+			.collect();
+		elements.sort_by(f);
+		for (slot, e) in self.children_mut().zip(elements) {
+			*slot = e;
+		}
+	}
+
+	/// Sort the child elements with a key extraction function.
+	///
+	/// See [`sort_children_by`](#method.sort_children_by) for how other kinds of child
+	/// nodes are handled.
+	pub fn sort_children_by_key<K: Ord, F: FnMut(&Element) -> K>(&mut self, mut f: F) {
+		self.sort_children_by(|a, b| f(a).cmp(&f(b)))
+	}
+
+	/// Remove consecutive child elements that are equal to the one before them, like
+	/// `Vec::dedup`.
+	///
+	/// Other kinds of child nodes between two elements are ignored when deciding whether
+	/// they are consecutive, and are always kept.
+	pub fn dedup_children(&mut self) {
+		let mut children = Vec::with_capacity(self.children.len());
+		let mut last_element = None;
+		for node in self.children.drain(..) {
+			if node.as_element().is_some() {
+				if last_element.is_some_and(|i| children[i] == node) {
+					continue;
+				}
+				last_element = Some(children.len());
+			}
+			children.push(node);
+		}
+		self.children = children;
+	}
+
+	/// Remove all child elements that have the same name as an earlier child element.
+	///
+	/// The first element with each name is kept, as are all other kinds of child nodes.
+	pub fn dedup_children_by_name(&mut self) {
+		let mut seen = std::collections::HashSet::new();
+		self.children.retain(|node| match node {
+			XMLNode::Element(e) => seen.insert(e.name.clone()),
+			_ => true,
+		});
+	}
+
+	/// Reverse the order of the child elements, returning this element so that further calls
+	/// can be chained.
+	///
+	/// As with [`sort_children_by`](#method.sort_children_by), child elements are reordered
+	/// among the positions occupied by elements, and other kinds of child nodes stay where
+	/// they are.
+	pub fn reverse_children(&mut self) -> &mut Element {
+		let positions: Vec<usize> = self
+			.children
+			.iter()
+			.enumerate()
+			.filter(|(_, node)| node.as_element().is_some())
+			.map(|(i, _)| i)
+			.collect();
+		let half = positions.len() / 2;
+		for (&a, &b) in positions[..half].iter().zip(positions.iter().rev()) {
+			self.children.swap(a, b);
+		}
+		self
+	}
+
+	/// Follow a sequence of child element names and return a reference to the element at
+	/// the end of the path.
+	///
+	/// At each level the first child with a matching name is chosen. An empty path
+	/// returns `self`.
+	pub fn get_path(&self, path: &[&str]) -> Option<&Element> {
+		let mut elem = self;
+		for name in path {
+			elem = elem.get_child(*name)?;
+		}
+		Some(elem)
+	}
+
+	/// Follow a sequence of child element names and return a mutable reference to the
+	/// element at the end of the path.
+	///
+	/// See [`get_path`](#method.get_path) for how the path is resolved.
+	pub fn get_mut_path(&mut self, path: &[&str]) -> Option<&mut Element> {
+		let mut elem = self;
+		for name in path {
+			elem = elem.get_mut_child(*name)?;
+		}
+		Some(elem)
+	}
+
+	/// Follow a sequence of child element names and return a reference to the element at
+	/// the end of the path.
+	///
+	/// # Panics
+	///
+	/// Panics if no element can be reached via the given path.
+	pub fn path(&self, path: &[&str]) -> &Element {
+		match self.get_path(path) {
+			Some(elem) => elem,
+			None => panic!("no element found at path {:?}", path),
+		}
+	}
+
+	/// Follow a sequence of child element names and return the text of the element at the
+	/// end of the path, if there is such an element and it has any text.
+	///
+	/// For example, `config.get_text_at_path(&["database", "host"])`.  See
+	/// [`get_path`](#method.get_path) and [`get_text`](#method.get_text).
+	pub fn get_text_at_path(&self, path: &[&str]) -> Option<Cow<'_, str>> {
+		self.get_path(path).and_then(Element::get_text)
+	}
+}
+
+/// An iterator that parses root elements from a stream of XML data one at a time.
+///
+/// Each call to `next` reads from the underlying reader until a complete root element
+/// has been parsed.  The iterator ends at the end of the document, or after the first
+/// error.  Nodes between root elements, such as comments, are discarded.
+///
+/// Elements are returned as soon as they are complete, so this can be used with streams
+/// that never end, such as network connections.
+///
+/// This is created by [`Element::parse_iter`](struct.Element.html#method.parse_iter).
+///
+/// # Example
+///
+/// ```
+/// use xmltree::Element;
+///
+/// let stream = "<message>hello</message><message>world</message>";
+/// for message in Element::parse_iter(stream.as_bytes()) {
+///     let message = message.unwrap();
+///     println!("{}", message.get_text().unwrap());
+/// }
+/// ```
+pub struct ElementIter<R: Read> {
+	reader: EventReader<SkipBom<R>>,
+	config: ParserConfig,
+	finished: bool,
+}
+
+impl<R: Read> ElementIter<R> {
+	/// Create an iterator over the root elements in the given data
+	pub fn new(r: R) -> ElementIter<R> {
+		ElementIter::new_with_config(r, ParserConfig::new())
+	}
+
+	/// Create an iterator over the root elements in the given data, using the provided
+	/// configuration
+	pub fn new_with_config(r: R, config: ParserConfig) -> ElementIter<R> {
+		ElementIter {
+			reader: config.create_reader(r),
+			config,
+			finished: false,
+		}
+	}
+}
+
+impl<R: Read> Iterator for ElementIter<R> {
+	type Item = Result<Element, ParseError>;
+
+	fn next(&mut self) -> Option<Result<Element, ParseError>> {
+		if self.finished {
+			return None;
+		}
+		match parse_root(&mut self.reader, &self.config, &mut Vec::new()) {
+			Ok(Some(elem)) => Some(Ok(elem)),
+			Ok(None) => {
+				self.finished = true;
+				None
+			}
+			Err(e) => {
+				self.finished = true;
+				Some(Err(e))
+			}
+		}
+	}
+}
+
+impl<R: Read> std::iter::FusedIterator for ElementIter<R> {}
+
+/// An iterator over the descendant elements of an `Element`, in depth-first pre-order.
+///
+/// This is created by [`Element::descendants`](struct.Element.html#method.descendants).
+#[derive(Debug, Clone)]
+pub struct Descendants<'a> {
+	stack: Vec<std::slice::Iter<'a, XMLNode>>,
+}
+
+impl<'a> Iterator for Descendants<'a> {
+	type Item = &'a Element;
+
+	fn next(&mut self) -> Option<&'a Element> {
+		loop {
+			match self.stack.last_mut()?.next() {
+				Some(XMLNode::Element(e)) => {
+					self.stack.push(e.children.iter());
+					return Some(e);
+				}
+				Some(_) => continue,
+				None => {
+					self.stack.pop();
+				}
+			}
+		}
+	}
+}
+
+/// A visitor over the elements of a tree, used with [`Element::accept`](struct.Element.html#method.accept).
+///
+/// For each element, `enter_element` and then `visit_element` are called before its
+/// children are visited (pre-order), and `leave_element` is called afterwards
+/// (post-order).  All methods do nothing by default, so implementors only need to
+/// override the ones they care about.
+pub trait Visitor {
+	/// Called on an element before its children are visited
+	fn enter_element(&mut self, _elem: &Element) {}
+
+	/// Called on an element after `enter_element`, before its children are visited
+	fn visit_element(&mut self, _elem: &Element) {}
+
+	/// Called on an element after its children have been visited
+	fn leave_element(&mut self, _elem: &Element) {}
+}
+
+/// A visitor that may modify the elements of a tree, used with
+/// [`Element::accept_mut`](struct.Element.html#method.accept_mut).
+///
+/// The methods are called in the same order as those of [`Visitor`](trait.Visitor.html).
+pub trait MutVisitor {
+	/// Called on an element before its children are visited
+	fn enter_element(&mut self, _elem: &mut Element) {}
+
+	/// Called on an element after `enter_element`, before its children are visited
+	fn visit_element(&mut self, _elem: &mut Element) {}
+
+	/// Called on an element after its children have been visited
+	fn leave_element(&mut self, _elem: &mut Element) {}
+}
+
+/// A visitor that does nothing
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultVisitor;
+
+impl Visitor for DefaultVisitor {}
+
+impl MutVisitor for DefaultVisitor {}
+
+/// A wrapper that formats an element as XML with `{:?}`, created by
+/// [`Element::debug_xml`](struct.Element.html#method.debug_xml)
+///
+/// The element is written as a single-line fragment, without the XML declaration.  With
+/// `{:#?}`, nested elements are indented instead.
+#[derive(Clone, Copy)]
+pub struct DebugXml<'a>(pub &'a Element);
+
+impl<'a> fmt::Debug for DebugXml<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let config = EmitterConfig::new()
+			.autopad_comments(false)
+			.write_document_declaration(false)
+			.perform_indent(f.alternate());
+		let mut buf = Vec::new();
+		self.0.write_with_config(&mut buf, config).map_err(|_| fmt::Error)?;
+		f.write_str(&String::from_utf8_lossy(&buf))
+	}
+}
+
+impl fmt::Display for Element {
+	/// Formats the element as an XML document, as written by [`write`](#method.write)
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let s = self.write_to_string().map_err(|_| fmt::Error)?;
+		f.write_str(&s)
+	}
+}
+
+impl FromStr for Element {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Element, ParseError> {
+		Element::parse(s.as_bytes())
+	}
+}
+
+impl<'a> Index<&'a str> for Element {
+	type Output = String;
+
+	/// Returns the value of the attribute with the given name
+	///
+	/// # Panics
+	///
+	/// Panics if the attribute is not present, see
+	/// [`get_attribute`](struct.Element.html#method.get_attribute) for a non-panicking version.
+	fn index(&self, name: &'a str) -> &String {
+		match self.attributes.get(name) {
+			Some(value) => value,
+			None => panic!("no attribute named {:?}", name),
+		}
+	}
+}
+
+impl<'a> IndexMut<&'a str> for Element {
+	/// Returns a mutable reference to the value of the attribute with the given name
+	///
+	/// # Panics
+	///
+	/// Panics if the attribute is not present; use
+	/// [`set_attribute`](struct.Element.html#method.set_attribute) to add a new attribute.
+	fn index_mut(&mut self, name: &'a str) -> &mut String {
+		match self.attributes.get_mut(name) {
+			Some(value) => value,
+			None => panic!("no attribute named {:?}", name),
+		}
+	}
+}
+
+impl<'a> From<&'a Element> for String {
+	/// Writes out the element as an XML document, see
+	/// [`Element::to_xml_string`](struct.Element.html#method.to_xml_string)
+	///
+	/// # Panics
+	///
+	/// Panics if the element cannot be written.
+	fn from(e: &'a Element) -> String {
+		e.to_xml_string()
+	}
+}
+
+impl From<Element> for String {
+	/// Writes out the element as an XML document, see
+	/// [`Element::to_xml_string`](struct.Element.html#method.to_xml_string)
+	///
+	/// # Panics
+	///
+	/// Panics if the element cannot be written.
+	fn from(e: Element) -> String {
+		e.to_xml_string()
+	}
+}
+
+impl<'a> TryFrom<&'a str> for Element {
+	type Error = ParseError;
+
+	fn try_from(s: &'a str) -> Result<Element, ParseError> {
+		Element::parse(s.as_bytes())
+	}
+}
+
+impl TryFrom<String> for Element {
+	type Error = ParseError;
+
+	fn try_from(s: String) -> Result<Element, ParseError> {
+		Element::parse(s.as_bytes())
+	}
+}
+
+/// A builder for constructing an `Element` with a fluent interface.
+///
+/// # Example
+///
+/// ```
+/// use xmltree::{Element, ElementBuilder};
+///
+/// let item = ElementBuilder::new("item")
+///     .attr("id", "1")
+///     .child(Element::builder("value").text("hello").build())
+///     .build();
+///
+/// assert_eq!(item.attributes["id"], "1");
+/// assert_eq!(item.get_child("value").unwrap().get_text().unwrap(), "hello");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ElementBuilder {
+	element: Element,
+}
+
+impl ElementBuilder {
+	/// Create a new builder for an element with the given name
+	pub fn new(name: &str) -> ElementBuilder {
+		ElementBuilder {
+			element: Element::new(name),
+		}
+	}
+
+	/// Set the name of the element
+	pub fn name<S: Into<String>>(mut self, name: S) -> ElementBuilder {
+		self.element.name = name.into();
+		self
+	}
+
+	/// Set the prefix of the element
+	pub fn prefix<S: Into<String>>(mut self, prefix: S) -> ElementBuilder {
+		self.element.prefix = Some(prefix.into());
+		self
+	}
+
+	/// Set the namespace of the element
+	pub fn namespace<S: Into<String>>(mut self, namespace: S) -> ElementBuilder {
+		self.element.namespace = Some(namespace.into());
+		self
+	}
+
+	/// Add an attribute, replacing any existing attribute with the same name
+	pub fn attr<K: Into<String>, V: Into<String>>(mut self, k: K, v: V) -> ElementBuilder {
+		self.element.attributes.insert(k.into(), v.into());
+		self
+	}
+
+	/// Append a text node
+	pub fn text<S: Into<String>>(mut self, text: S) -> ElementBuilder {
+		self.element.children.push(XMLNode::Text(text.into()));
+		self
+	}
+
+	/// Append a child element
+	pub fn child(mut self, child: Element) -> ElementBuilder {
+		self.element.children.push(XMLNode::Element(child));
+		self
+	}
+
+	/// Finish building and return the element
+	pub fn build(self) -> Element {
+		self.element
 	}
 }
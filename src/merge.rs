@@ -0,0 +1,154 @@
+//! Merging of element trees
+//!
+//! This is useful for configuration that can be overridden at several levels, where each
+//! level is an XML document with the same root element:
+//!
+//! ```
+//! use xmltree::Element;
+//! use xmltree::merge::merge;
+//!
+//! let system: Element = r#"<config><host>localhost</host><port>80</port></config>"#.parse().unwrap();
+//! let user: Element = r#"<config><port>8080</port><user>bob</user></config>"#.parse().unwrap();
+//!
+//! let config = merge(system, user).unwrap();
+//! assert_eq!(config.get_child_text("host").unwrap(), "localhost");
+//! assert_eq!(config.get_child_text("port").unwrap(), "8080");
+//! assert_eq!(config.get_child_text("user").unwrap(), "bob");
+//! ```
+
+use std::fmt;
+
+use super::{Element, XMLNode};
+
+/// What to do when a child element of the overlay has the same name as one in the base
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collision {
+	/// Merge the two elements recursively (the default)
+	Merge,
+	/// Replace the base element with the overlay element
+	Replace,
+	/// Keep the base element and append the overlay element after the other children
+	Append,
+	/// Fail with `MergeError::Collision`
+	Error,
+}
+
+/// Options for [`merge_with_config`](fn.merge_with_config.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConfig {
+	/// What to do with child elements that appear in both trees.  Defaults to
+	/// `Collision::Merge`.
+	pub on_collision: Collision,
+}
+
+impl MergeConfig {
+	/// Returns a new config with the default options
+	pub fn new() -> MergeConfig {
+		MergeConfig {
+			on_collision: Collision::Merge,
+		}
+	}
+}
+
+impl Default for MergeConfig {
+	fn default() -> MergeConfig {
+		MergeConfig::new()
+	}
+}
+
+/// Errors that can occur merging two trees
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeError {
+	/// The root elements have different names
+	RootMismatch { base: String, overlay: String },
+	/// A child element with the given name appeared in both trees, and the config
+	/// asked for this to be an error
+	Collision(String),
+}
+
+impl fmt::Display for MergeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			MergeError::RootMismatch { ref base, ref overlay } => {
+				write!(f, "Cannot merge <{}> into <{}>", overlay, base)
+			}
+			MergeError::Collision(ref name) => write!(f, "Child element <{}> appears in both trees", name),
+		}
+	}
+}
+
+impl std::error::Error for MergeError {}
+
+/// Merges `overlay` into `base`, using the default config
+///
+/// See [`merge_with_config`](fn.merge_with_config.html) for details.
+pub fn merge(base: Element, overlay: Element) -> Result<Element, MergeError> {
+	merge_with_config(base, overlay, &MergeConfig::new())
+}
+
+/// Merges `overlay` into `base`, returning the combined tree
+///
+/// The root elements must have the same name.  Attributes of `overlay` override those of
+/// `base`, and if `overlay` has any text it replaces that of `base`.  Each child element of
+/// `overlay` is matched with the child of `base` that has the same name and occurrence
+/// (the second `<item>` of `overlay` is matched with the second `<item>` of `base`);
+/// matched children are handled according to `config.on_collision`, and unmatched ones are
+/// appended.  Other kinds of nodes in `overlay`, such as comments, are discarded.
+pub fn merge_with_config(base: Element, overlay: Element, config: &MergeConfig) -> Result<Element, MergeError> {
+	if base.name != overlay.name {
+		return Err(MergeError::RootMismatch {
+			base: base.name,
+			overlay: overlay.name,
+		});
+	}
+	merge_element(base, overlay, config)
+}
+
+fn merge_element(mut base: Element, overlay: Element, config: &MergeConfig) -> Result<Element, MergeError> {
+	if let Some(text) = overlay.get_text() {
+		base.set_text(text.into_owned());
+	}
+	base.attributes.extend(overlay.attributes);
+	base.attributes_ns.extend(overlay.attributes_ns);
+
+	let mut seen: Vec<(String, usize)> = Vec::new();
+	for node in overlay.children {
+		let child = match node {
+			XMLNode::Element(child) => child,
+			_ => continue,
+		};
+
+		// find the base child with the same name and occurrence as this one
+		let occurrence = match seen.iter_mut().find(|(name, _)| *name == child.name) {
+			Some((_, count)) => {
+				*count += 1;
+				*count - 1
+			}
+			None => {
+				seen.push((child.name.clone(), 1));
+				0
+			}
+		};
+		let position = base
+			.children
+			.iter()
+			.enumerate()
+			.filter(|(_, node)| node.as_element().is_some_and(|e| e.name == child.name))
+			.map(|(i, _)| i)
+			.nth(occurrence);
+
+		match (position, config.on_collision) {
+			(None, _) | (Some(_), Collision::Append) => base.children.push(XMLNode::Element(child)),
+			(Some(i), Collision::Replace) => base.children[i] = XMLNode::Element(child),
+			(Some(i), Collision::Merge) => {
+				if let XMLNode::Element(ref mut existing) = base.children[i] {
+					let e = std::mem::take(existing);
+					*existing = merge_element(e, child, config)?;
+				}
+			}
+			(Some(_), Collision::Error) => return Err(MergeError::Collision(child.name)),
+		}
+	}
+
+	Ok(base)
+}
@@ -0,0 +1,354 @@
+//! A small subset of XPath for selecting elements, see `Element::select`
+
+use std::collections::HashSet;
+use std::fmt;
+
+use super::Element;
+
+/// Errors that can occur evaluating a path expression with
+/// [`Element::select`](struct.Element.html#method.select)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectError {
+	/// The expression is invalid.  Holds the byte offset at which the problem was found and
+	/// a description of it.
+	Syntax(usize, &'static str),
+	/// The expression ends with an attribute step (such as `@id`), but elements were
+	/// requested
+	UnexpectedAttributeStep,
+	/// The expression does not end with an attribute step, but attribute values were
+	/// requested
+	MissingAttributeStep,
+}
+
+impl fmt::Display for SelectError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			SelectError::Syntax(pos, msg) => write!(f, "Invalid path expression at offset {}: {}", pos, msg),
+			SelectError::UnexpectedAttributeStep => write!(f, "Path expression selects attributes, not elements"),
+			SelectError::MissingAttributeStep => write!(f, "Path expression does not select attributes"),
+		}
+	}
+}
+
+impl std::error::Error for SelectError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+	Child,
+	Descendant,
+}
+
+#[derive(Debug)]
+enum NameTest {
+	Any,
+	Name(String),
+	Context,
+}
+
+#[derive(Debug)]
+enum Predicate {
+	HasAttribute(String),
+	AttributeEquals(String, String),
+	Position(usize),
+}
+
+#[derive(Debug)]
+struct Step {
+	axis: Axis,
+	test: NameTest,
+	predicates: Vec<Predicate>,
+}
+
+#[derive(Debug)]
+struct Path {
+	absolute: bool,
+	steps: Vec<Step>,
+	/// A final attribute step, with the axis of the elements it applies to
+	attribute: Option<(Axis, String)>,
+}
+
+struct Parser<'a> {
+	expr: &'a str,
+	pos: usize,
+}
+
+impl<'a> Parser<'a> {
+	fn rest(&self) -> &'a str {
+		&self.expr[self.pos..]
+	}
+
+	fn eat(&mut self, s: &str) -> bool {
+		if self.rest().starts_with(s) {
+			self.pos += s.len();
+			true
+		} else {
+			false
+		}
+	}
+
+	fn error<T>(&self, msg: &'static str) -> Result<T, SelectError> {
+		Err(SelectError::Syntax(self.pos, msg))
+	}
+
+	fn name(&mut self) -> Result<String, SelectError> {
+		let len = self
+			.rest()
+			.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.' || c == ':'))
+			.unwrap_or_else(|| self.rest().len());
+		let name = &self.rest()[..len];
+		// `child::` and friends must not be mistaken for part of a name
+		let name = name.split("::").next().unwrap_or("");
+		if name.is_empty() {
+			return self.error("expected a name");
+		}
+		self.pos += name.len();
+		Ok(name.to_owned())
+	}
+
+	fn predicate(&mut self) -> Result<Predicate, SelectError> {
+		if self.eat("@") {
+			let name = self.name()?;
+			if !self.eat("=") {
+				return Ok(Predicate::HasAttribute(name));
+			}
+			let quote = match self.rest().chars().next() {
+				Some(q @ '\'') | Some(q @ '"') => q,
+				_ => return self.error("expected a quoted value"),
+			};
+			self.pos += 1;
+			let len = match self.rest().find(quote) {
+				Some(len) => len,
+				None => return self.error("unterminated string"),
+			};
+			let value = self.rest()[..len].to_owned();
+			self.pos += len + 1;
+			Ok(Predicate::AttributeEquals(name, value))
+		} else {
+			let len = self.rest().find(|c: char| !c.is_ascii_digit()).unwrap_or(self.rest().len());
+			match self.rest()[..len].parse() {
+				Ok(n) if n > 0 => {
+					self.pos += len;
+					Ok(Predicate::Position(n))
+				}
+				_ => self.error("expected an attribute test or a position"),
+			}
+		}
+	}
+
+	fn step(&mut self, axis: Axis) -> Result<Step, SelectError> {
+		let axis = if self.eat("child::") {
+			Axis::Child
+		} else if self.eat("descendant::") {
+			Axis::Descendant
+		} else {
+			axis
+		};
+
+		let test = if self.eat("*") {
+			NameTest::Any
+		} else if self.eat(".") {
+			NameTest::Context
+		} else {
+			NameTest::Name(self.name()?)
+		};
+
+		let mut predicates = Vec::new();
+		while self.eat("[") {
+			predicates.push(self.predicate()?);
+			if !self.eat("]") {
+				return self.error("expected `]`");
+			}
+		}
+
+		Ok(Step { axis, test, predicates })
+	}
+
+	fn path(mut self) -> Result<Path, SelectError> {
+		let mut axis = Axis::Child;
+		let absolute = if self.eat("//") {
+			axis = Axis::Descendant;
+			true
+		} else {
+			self.eat("/")
+		};
+
+		let mut steps = Vec::new();
+		let mut attribute = None;
+		loop {
+			if self.eat("@") {
+				attribute = Some((axis, self.name()?));
+				if !self.rest().is_empty() {
+					return self.error("an attribute step must be the last step");
+				}
+				break;
+			}
+			steps.push(self.step(axis)?);
+			if self.rest().is_empty() {
+				break;
+			}
+			axis = if self.eat("//") {
+				Axis::Descendant
+			} else if self.eat("/") {
+				Axis::Child
+			} else {
+				return self.error("expected `/`");
+			};
+		}
+
+		Ok(Path {
+			absolute,
+			steps,
+			attribute,
+		})
+	}
+}
+
+fn attribute_value<'a>(e: &'a Element, name: &str) -> Option<&'a str> {
+	e.get_attribute(name).or_else(|| {
+		e.attributes_ns
+			.iter()
+			.find(|(k, _)| k.to_string() == name)
+			.map(|(_, v)| v.as_str())
+	})
+}
+
+fn matches_name(e: &Element, name: &str) -> bool {
+	if name.contains(':') {
+		e.qualified_name() == name
+	} else {
+		e.name == name
+	}
+}
+
+impl Step {
+	fn matches(&self, e: &Element) -> bool {
+		match self.test {
+			NameTest::Any | NameTest::Context => true,
+			NameTest::Name(ref name) => matches_name(e, name),
+		}
+	}
+
+	/// Applies this step to a single context element.  `document` is set for the first
+	/// step of an absolute path, whose context is the (implied) document containing `e`.
+	fn apply<'a>(&self, e: &'a Element, document: bool) -> Vec<&'a Element> {
+		let candidates: Vec<&'a Element> = match (&self.test, self.axis, document) {
+			(NameTest::Context, _, _) => vec![e],
+			(_, Axis::Child, true) => vec![e],
+			(_, Axis::Descendant, true) => std::iter::once(e).chain(e.descendants()).collect(),
+			(_, Axis::Child, false) => e.child_elements().collect(),
+			(_, Axis::Descendant, false) => e.descendants().collect(),
+		};
+
+		let mut selected: Vec<&'a Element> = candidates.into_iter().filter(|e| self.matches(e)).collect();
+		for predicate in &self.predicates {
+			selected = match *predicate {
+				Predicate::HasAttribute(ref name) => selected
+					.into_iter()
+					.filter(|e| attribute_value(e, name).is_some())
+					.collect(),
+				Predicate::AttributeEquals(ref name, ref value) => selected
+					.into_iter()
+					.filter(|e| attribute_value(e, name) == Some(value.as_str()))
+					.collect(),
+				Predicate::Position(n) => selected.into_iter().nth(n - 1).into_iter().collect(),
+			};
+		}
+		selected
+	}
+}
+
+impl Path {
+	fn parse(expr: &str) -> Result<Path, SelectError> {
+		if expr.is_empty() {
+			return Err(SelectError::Syntax(0, "empty expression"));
+		}
+		Parser { expr, pos: 0 }.path()
+	}
+
+	fn evaluate<'a>(&self, root: &'a Element) -> Vec<&'a Element> {
+		let mut context = vec![root];
+		for (i, step) in self.steps.iter().enumerate() {
+			let mut seen = HashSet::new();
+			let mut next = Vec::new();
+			for e in context {
+				for selected in step.apply(e, i == 0 && self.absolute) {
+					if seen.insert(selected as *const Element) {
+						next.push(selected);
+					}
+				}
+			}
+			context = next;
+		}
+		context
+	}
+}
+
+impl Element {
+	/// Selects elements using a path expression, a small subset of XPath
+	///
+	/// An expression is a sequence of steps separated by `/` (selecting children) or `//`
+	/// (selecting descendants).  Each step is a name (matched against the local name, or
+	/// against the qualified name if it contains `:`), `*` for any element, or `.` for the
+	/// context element itself.  A step may have an explicit `child::` or `descendant::` axis,
+	/// and any number of predicates: `[@attr]`, `[@attr='value']` or a 1-based position
+	/// such as `[2]`.
+	///
+	/// A relative expression starts from this element, so `foo/bar` selects the `bar`
+	/// children of its `foo` children.  An expression starting with `/` treats this element
+	/// as the root of the document, so `/root/foo` selects `foo` children of this element
+	/// if it is named `root`, and `//foo` selects this element and its descendants named
+	/// `foo`.
+	///
+	/// The selected elements are returned in the order they were found, without
+	/// duplicates.
+	///
+	/// # Example
+	///
+	/// ```
+	/// use xmltree::Element;
+	///
+	/// let e: Element = r#"<lib><book id="1"><title>A</title></book><book id="2"><title>B</title></book></lib>"#
+	///     .parse()
+	///     .unwrap();
+	/// let titles = e.select("book[@id='2']/title").unwrap();
+	/// assert_eq!(titles[0].get_text().unwrap(), "B");
+	/// assert_eq!(e.select("//title").unwrap().len(), 2);
+	/// ```
+	pub fn select(&self, expr: &str) -> Result<Vec<&Element>, SelectError> {
+		let path = Path::parse(expr)?;
+		if path.attribute.is_some() {
+			return Err(SelectError::UnexpectedAttributeStep);
+		}
+		Ok(path.evaluate(self))
+	}
+
+	/// Selects the first element matching a path expression, see
+	/// [`select`](#method.select).
+	pub fn select_one(&self, expr: &str) -> Result<Option<&Element>, SelectError> {
+		Ok(self.select(expr)?.into_iter().next())
+	}
+
+	/// Selects attribute values using a path expression that ends with an attribute step,
+	/// such as `book/@id`
+	///
+	/// See [`select`](#method.select) for the syntax.  Elements that do not have the
+	/// attribute are skipped.
+	pub fn select_values(&self, expr: &str) -> Result<Vec<&str>, SelectError> {
+		let path = Path::parse(expr)?;
+		let (axis, name) = match path.attribute {
+			Some((axis, ref name)) => (axis, name),
+			None => return Err(SelectError::MissingAttributeStep),
+		};
+		let mut elements = path.evaluate(self);
+		if axis == Axis::Descendant {
+			// `//@attr` applies to the elements and all of their descendants
+			let mut seen = HashSet::new();
+			elements = elements
+				.into_iter()
+				.flat_map(|e| std::iter::once(e).chain(e.descendants()))
+				.filter(|e| seen.insert(*e as *const Element))
+				.collect();
+		}
+		Ok(elements.into_iter().filter_map(|e| attribute_value(e, name)).collect())
+	}
+}
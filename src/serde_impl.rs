@@ -0,0 +1,324 @@
+//! `Serialize` and `Deserialize` implementations for `Element`
+//!
+//! An element is represented as a struct with the fields `name`, `prefix`, `namespace`,
+//! `namespaces`, `declared_namespaces`, `attributes`, `attributes_ns` and `children`.
+//! When deserializing, only `name` is required; all other fields default to empty.
+//!
+//! Namespaced attributes are represented as a sequence of `(name, value)` pairs, where each
+//! name is a struct with the fields `prefix`, `namespace` and `local_name`.
+//!
+//! Child nodes are represented as externally tagged enum variants, e.g.
+//! `{"Text": "hello"}` or `{"Element": {"name": "foo"}}`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{
+	self, Deserialize, Deserializer, EnumAccess, IgnoredAny, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use serde::ser::{Serialize, SerializeStruct, SerializeTupleVariant, Serializer};
+
+use super::{Element, IndexMap, Namespace, QName, XMLNode};
+
+const FIELDS: &[&str] = &[
+	"name",
+	"prefix",
+	"namespace",
+	"namespaces",
+	"declared_namespaces",
+	"attributes",
+	"attributes_ns",
+	"children",
+];
+
+const QNAME_FIELDS: &[&str] = &["prefix", "namespace", "local_name"];
+
+const VARIANTS: &[&str] = &["Element", "Text", "CData", "Comment", "ProcessingInstruction"];
+
+struct NamespaceMap<'a>(&'a Option<Namespace>);
+
+impl<'a> Serialize for NamespaceMap<'a> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match *self.0 {
+			Some(ref ns) => serializer.collect_map(&ns.0),
+			None => serializer.collect_map(&BTreeMap::<String, String>::new()),
+		}
+	}
+}
+
+impl Serialize for Element {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut state = serializer.serialize_struct("Element", FIELDS.len())?;
+		state.serialize_field("name", &self.name)?;
+		state.serialize_field("prefix", &self.prefix)?;
+		state.serialize_field("namespace", &self.namespace)?;
+		state.serialize_field("namespaces", &NamespaceMap(&self.namespaces))?;
+		state.serialize_field("declared_namespaces", &NamespaceMap(&self.declared_namespaces))?;
+		state.serialize_field("attributes", &self.attributes)?;
+		state.serialize_field("attributes_ns", &AttributesNs(&self.attributes_ns))?;
+		state.serialize_field("children", &self.children)?;
+		state.end()
+	}
+}
+
+struct AttributesNs<'a>(&'a IndexMap<QName, String>);
+
+impl<'a> Serialize for AttributesNs<'a> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(self.0.iter())
+	}
+}
+
+fn to_attributes_ns(pairs: Vec<(QName, String)>) -> IndexMap<QName, String> {
+	pairs.into_iter().collect()
+}
+
+impl Serialize for QName {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut state = serializer.serialize_struct("QName", QNAME_FIELDS.len())?;
+		state.serialize_field("prefix", &self.prefix)?;
+		state.serialize_field("namespace", &self.namespace)?;
+		state.serialize_field("local_name", &self.local_name)?;
+		state.end()
+	}
+}
+
+struct QNameVisitor;
+
+impl<'de> Visitor<'de> for QNameVisitor {
+	type Value = QName;
+
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "a qualified name")
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<QName, A::Error> {
+		let prefix = seq.next_element()?.unwrap_or(None);
+		let namespace = seq.next_element()?.unwrap_or(None);
+		let local_name = seq
+			.next_element()?
+			.ok_or_else(|| de::Error::invalid_length(2, &self))?;
+		Ok(QName {
+			prefix,
+			namespace,
+			local_name,
+		})
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<QName, A::Error> {
+		let mut prefix = None;
+		let mut namespace = None;
+		let mut local_name = None;
+
+		while let Some(key) = map.next_key::<String>()? {
+			match key.as_str() {
+				"prefix" => prefix = map.next_value()?,
+				"namespace" => namespace = map.next_value()?,
+				"local_name" => local_name = Some(map.next_value()?),
+				_ => {
+					map.next_value::<IgnoredAny>()?;
+				}
+			}
+		}
+
+		let local_name = local_name.ok_or_else(|| de::Error::missing_field("local_name"))?;
+		Ok(QName {
+			prefix,
+			namespace,
+			local_name,
+		})
+	}
+}
+
+impl<'de> Deserialize<'de> for QName {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<QName, D::Error> {
+		deserializer.deserialize_struct("QName", QNAME_FIELDS, QNameVisitor)
+	}
+}
+
+fn to_namespaces(map: BTreeMap<String, String>) -> Option<Namespace> {
+	let ns = Namespace(map);
+	if ns.is_essentially_empty() {
+		None
+	} else {
+		Some(ns)
+	}
+}
+
+struct ElementVisitor;
+
+impl<'de> Visitor<'de> for ElementVisitor {
+	type Value = Element;
+
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "an XML element")
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Element, A::Error> {
+		let name: String = seq
+			.next_element()?
+			.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+		let mut elem = Element::new(&name);
+		elem.prefix = seq.next_element()?.unwrap_or(None);
+		elem.namespace = seq.next_element()?.unwrap_or(None);
+		elem.namespaces = to_namespaces(seq.next_element()?.unwrap_or_default());
+		elem.declared_namespaces = to_namespaces(seq.next_element()?.unwrap_or_default());
+		elem.attributes = seq.next_element()?.unwrap_or_default();
+		elem.attributes_ns = to_attributes_ns(seq.next_element()?.unwrap_or_default());
+		elem.children = seq.next_element()?.unwrap_or_default();
+		Ok(elem)
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Element, A::Error> {
+		let mut name: Option<String> = None;
+		let mut prefix = None;
+		let mut namespace = None;
+		let mut namespaces = BTreeMap::new();
+		let mut declared_namespaces = BTreeMap::new();
+		let mut attributes = IndexMap::new();
+		let mut attributes_ns = Vec::new();
+		let mut children = Vec::new();
+
+		while let Some(key) = map.next_key::<String>()? {
+			match key.as_str() {
+				"name" => name = Some(map.next_value()?),
+				"prefix" => prefix = map.next_value()?,
+				"namespace" => namespace = map.next_value()?,
+				"namespaces" => namespaces = map.next_value()?,
+				"declared_namespaces" => declared_namespaces = map.next_value()?,
+				"attributes" => attributes = map.next_value()?,
+				"attributes_ns" => attributes_ns = map.next_value()?,
+				"children" => children = map.next_value()?,
+				_ => {
+					map.next_value::<IgnoredAny>()?;
+				}
+			}
+		}
+
+		let name = name.ok_or_else(|| de::Error::missing_field("name"))?;
+		Ok(Element {
+			prefix,
+			namespace,
+			namespaces: to_namespaces(namespaces),
+			declared_namespaces: to_namespaces(declared_namespaces),
+			name,
+			attributes,
+			attributes_ns: to_attributes_ns(attributes_ns),
+			children,
+		})
+	}
+}
+
+impl<'de> Deserialize<'de> for Element {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Element, D::Error> {
+		deserializer.deserialize_struct("Element", FIELDS, ElementVisitor)
+	}
+}
+
+impl Serialize for XMLNode {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match *self {
+			XMLNode::Element(ref e) => serializer.serialize_newtype_variant("XMLNode", 0, VARIANTS[0], e),
+			XMLNode::Text(ref s) => serializer.serialize_newtype_variant("XMLNode", 1, VARIANTS[1], s),
+			XMLNode::CData(ref s) => serializer.serialize_newtype_variant("XMLNode", 2, VARIANTS[2], s),
+			XMLNode::Comment(ref s) => serializer.serialize_newtype_variant("XMLNode", 3, VARIANTS[3], s),
+			XMLNode::ProcessingInstruction(ref name, ref data) => {
+				let mut state = serializer.serialize_tuple_variant("XMLNode", 4, VARIANTS[4], 2)?;
+				state.serialize_field(name)?;
+				state.serialize_field(data)?;
+				state.end()
+			}
+		}
+	}
+}
+
+enum NodeKind {
+	Element,
+	Text,
+	CData,
+	Comment,
+	ProcessingInstruction,
+}
+
+struct NodeKindVisitor;
+
+impl<'de> Visitor<'de> for NodeKindVisitor {
+	type Value = NodeKind;
+
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "an XML node kind")
+	}
+
+	fn visit_u64<E: de::Error>(self, v: u64) -> Result<NodeKind, E> {
+		match v {
+			0 => Ok(NodeKind::Element),
+			1 => Ok(NodeKind::Text),
+			2 => Ok(NodeKind::CData),
+			3 => Ok(NodeKind::Comment),
+			4 => Ok(NodeKind::ProcessingInstruction),
+			_ => Err(de::Error::invalid_value(de::Unexpected::Unsigned(v), &self)),
+		}
+	}
+
+	fn visit_str<E: de::Error>(self, v: &str) -> Result<NodeKind, E> {
+		match v {
+			"Element" => Ok(NodeKind::Element),
+			"Text" => Ok(NodeKind::Text),
+			"CData" => Ok(NodeKind::CData),
+			"Comment" => Ok(NodeKind::Comment),
+			"ProcessingInstruction" => Ok(NodeKind::ProcessingInstruction),
+			_ => Err(de::Error::unknown_variant(v, VARIANTS)),
+		}
+	}
+}
+
+impl<'de> Deserialize<'de> for NodeKind {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<NodeKind, D::Error> {
+		deserializer.deserialize_identifier(NodeKindVisitor)
+	}
+}
+
+struct ProcessingInstructionVisitor;
+
+impl<'de> Visitor<'de> for ProcessingInstructionVisitor {
+	type Value = XMLNode;
+
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "a processing instruction target and data")
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<XMLNode, A::Error> {
+		let name = seq
+			.next_element()?
+			.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+		let data = seq.next_element()?.unwrap_or(None);
+		Ok(XMLNode::ProcessingInstruction(name, data))
+	}
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+	type Value = XMLNode;
+
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "an XML node")
+	}
+
+	fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<XMLNode, A::Error> {
+		let (kind, variant) = data.variant()?;
+		match kind {
+			NodeKind::Element => variant.newtype_variant().map(XMLNode::Element),
+			NodeKind::Text => variant.newtype_variant().map(XMLNode::Text),
+			NodeKind::CData => variant.newtype_variant().map(XMLNode::CData),
+			NodeKind::Comment => variant.newtype_variant().map(XMLNode::Comment),
+			NodeKind::ProcessingInstruction => variant.tuple_variant(2, ProcessingInstructionVisitor),
+		}
+	}
+}
+
+impl<'de> Deserialize<'de> for XMLNode {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<XMLNode, D::Error> {
+		deserializer.deserialize_enum("XMLNode", VARIANTS, NodeVisitor)
+	}
+}
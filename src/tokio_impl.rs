@@ -0,0 +1,52 @@
+//! Parsing from a `tokio` async reader, see `Element::parse_async`
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
+use super::{Element, ParseError};
+
+/// The future returned by [`Element::parse_async`](../struct.Element.html#method.parse_async)
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct ParseAsync<R> {
+	reader: R,
+	buf: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> Future for ParseAsync<R> {
+	type Output = Result<Element, ParseError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<Element, ParseError>> {
+		let this = &mut *self;
+		let mut chunk = [0; 8192];
+		loop {
+			let mut read = ReadBuf::new(&mut chunk);
+			match Pin::new(&mut this.reader).poll_read(cx, &mut read) {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(Err(e)) => return Poll::Ready(Err(xml::reader::Error::from(e).into())),
+				Poll::Ready(Ok(())) if read.filled().is_empty() => {
+					return Poll::Ready(Element::parse(&this.buf[..]));
+				}
+				Poll::Ready(Ok(())) => this.buf.extend_from_slice(read.filled()),
+			}
+		}
+	}
+}
+
+impl Element {
+	/// Reads all data from an async reader and parses it into an Element
+	///
+	/// As `xml-rs` is synchronous, the whole input is buffered before it is parsed with
+	/// [`parse`](#method.parse).  A read error is reported as a `MalformedXml` error.
+	///
+	/// This requires the `tokio` feature.
+	pub fn parse_async<R: AsyncRead + Unpin>(r: R) -> ParseAsync<R> {
+		ParseAsync {
+			reader: r,
+			buf: Vec::new(),
+		}
+	}
+}
@@ -0,0 +1,136 @@
+//! Utilities for working with XML text
+
+use std::borrow::Cow;
+
+/// Escapes the characters `&`, `<`, `>`, `"` and `'` in the given string using the
+/// predefined XML entities
+///
+/// The result can be used both as text content and as an attribute value.  If there is
+/// nothing to escape, the string is returned as is.
+pub fn escape_xml(s: &str) -> Cow<'_, str> {
+	if !s.contains(['&', '<', '>', '"', '\'']) {
+		return Cow::Borrowed(s);
+	}
+
+	let mut escaped = String::with_capacity(s.len() + 8);
+	for c in s.chars() {
+		match c {
+			'&' => escaped.push_str("&amp;"),
+			'<' => escaped.push_str("&lt;"),
+			'>' => escaped.push_str("&gt;"),
+			'"' => escaped.push_str("&quot;"),
+			'\'' => escaped.push_str("&apos;"),
+			c => escaped.push(c),
+		}
+	}
+	Cow::Owned(escaped)
+}
+
+/// Decodes the predefined XML entities (`&amp;`, `&lt;`, `&gt;`, `&quot;` and `&apos;`)
+/// and numeric character references (such as `&#65;` or `&#x41;`) in the given string
+///
+/// Anything that is not a valid reference, such as an unknown entity or a reference to an
+/// invalid character, is left unchanged.  If there is nothing to decode, the string is
+/// returned as is.
+pub fn unescape_xml(s: &str) -> Cow<'_, str> {
+	if !s.contains('&') {
+		return Cow::Borrowed(s);
+	}
+
+	let mut unescaped = String::with_capacity(s.len());
+	let mut rest = s;
+	while let Some(start) = rest.find('&') {
+		unescaped.push_str(&rest[..start]);
+		rest = &rest[start..];
+
+		let decoded = rest.find(';').and_then(|end| Some((decode_reference(&rest[1..end])?, end)));
+		match decoded {
+			Some((c, end)) => {
+				unescaped.push(c);
+				rest = &rest[end + 1..];
+			}
+			None => {
+				unescaped.push('&');
+				rest = &rest[1..];
+			}
+		}
+	}
+	unescaped.push_str(rest);
+	Cow::Owned(unescaped)
+}
+
+/// Decodes the name of a reference, without the surrounding `&` and `;`
+pub(crate) fn decode_reference(name: &str) -> Option<char> {
+	match name {
+		"amp" => Some('&'),
+		"lt" => Some('<'),
+		"gt" => Some('>'),
+		"quot" => Some('"'),
+		"apos" => Some('\''),
+		_ => {
+			let (digits, radix) = match name.strip_prefix("#x") {
+				Some(hex) => (hex, 16),
+				None => (name.strip_prefix('#')?, 10),
+			};
+			if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+				return None;
+			}
+			let code = u32::from_str_radix(digits, radix).ok()?;
+			std::char::from_u32(code).filter(|&c| is_xml_char(c))
+		}
+	}
+}
+
+/// Returns `true` if the character matches the XML 1.0 `Char` production
+fn is_xml_char(c: char) -> bool {
+	matches!(c, '\u{9}' | '\u{A}' | '\u{D}' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..)
+}
+
+/// Returns `true` if the string is a valid XML 1.0 name, as used for element and attribute
+/// names
+///
+/// A name must be non-empty, start with a letter, `_` or `:` (or one of a number of other
+/// Unicode characters), and contain only those characters together with digits, `-`, `.`
+/// and some combining characters.
+pub fn is_valid_xml_name(s: &str) -> bool {
+	let mut chars = s.chars();
+	match chars.next() {
+		Some(c) if is_name_start_char(c) => chars.all(is_name_char),
+		_ => false,
+	}
+}
+
+/// Asserts that the string is a valid XML name in debug builds, see
+/// [`is_valid_xml_name`](fn.is_valid_xml_name.html)
+///
+/// # Panics
+///
+/// In debug builds, panics if `s` is not a valid XML name.  Does nothing in release builds.
+pub fn assert_valid_xml_name(s: &str) {
+	debug_assert!(is_valid_xml_name(s), "{:?} is not a valid XML name", s);
+}
+
+/// The XML 1.0 `NameStartChar` production
+fn is_name_start_char(c: char) -> bool {
+	matches!(c,
+		':' | 'A'..='Z' | '_' | 'a'..='z'
+		| '\u{C0}'..='\u{D6}'
+		| '\u{D8}'..='\u{F6}'
+		| '\u{F8}'..='\u{2FF}'
+		| '\u{370}'..='\u{37D}'
+		| '\u{37F}'..='\u{1FFF}'
+		| '\u{200C}'..='\u{200D}'
+		| '\u{2070}'..='\u{218F}'
+		| '\u{2C00}'..='\u{2FEF}'
+		| '\u{3001}'..='\u{D7FF}'
+		| '\u{F900}'..='\u{FDCF}'
+		| '\u{FDF0}'..='\u{FFFD}'
+		| '\u{10000}'..='\u{EFFFF}'
+	)
+}
+
+/// The XML 1.0 `NameChar` production
+fn is_name_char(c: char) -> bool {
+	is_name_start_char(c)
+		|| matches!(c, '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
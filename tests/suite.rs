@@ -1,3 +1,8 @@
+#[cfg(any(feature = "serde", feature = "serde_json"))]
+extern crate serde_json;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+extern crate xml;
 extern crate xmltree;
 
 use xmltree::*;
@@ -129,7 +134,7 @@ fn test_new() {
     assert_eq!(e.name.as_str(), "foo");
     assert_eq!(e.attributes.len(), 0);
     assert_eq!(e.children.len(), 0);
-    assert_eq!(e.text, None);
+    assert_eq!(e.get_text(), None);
 }
 
 #[test]
@@ -204,3 +209,1877 @@ fn test_write_with_config() {
     let s = String::from_utf8(buf).unwrap();
     println!("{}", s);
 }
+
+#[test]
+fn test_get_all_children() {
+    let data = r##"
+        <list>
+            <item id="1" />
+            <other />
+            <item id="2" />
+        </list>
+    "##;
+
+    let mut e = Element::parse(data.as_bytes()).unwrap();
+    {
+        let ids: Vec<&str> = e.get_all_children("item")
+            .map(|c| c.attributes["id"].as_str())
+            .collect();
+        assert_eq!(ids, vec!["1", "2"]);
+    }
+    assert_eq!(e.get_all_children("doesnotexist").count(), 0);
+
+    for item in e.get_all_children_mut("item") {
+        item.attributes.insert("seen".to_owned(), "yes".to_owned());
+    }
+    assert!(e.get_all_children("item").all(|c| c.attributes.contains_key("seen")));
+    assert!(!e.get_child("other").unwrap().attributes.contains_key("seen"));
+}
+
+#[test]
+fn test_take_all_children() {
+    let data = r##"
+        <list>
+            <item id="1" />
+            <a />
+            <item id="2" />
+            <b />
+        </list>
+    "##;
+
+    let mut e = Element::parse(data.as_bytes()).unwrap();
+    let taken = e.take_all_children("item");
+    assert_eq!(taken.len(), 2);
+    assert_eq!(taken[0].attributes["id"], "1");
+    assert_eq!(taken[1].attributes["id"], "2");
+
+    let names: Vec<&str> = e.child_elements().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b"]);
+
+    assert!(e.take_all_children("item").is_empty());
+    assert_eq!(e.children.len(), 2);
+}
+
+#[test]
+fn test_get_path() {
+    let data = r##"
+        <config>
+            <server>
+                <address>
+                    <port>8080</port>
+                </address>
+            </server>
+        </config>
+    "##;
+
+    let mut e = Element::parse(data.as_bytes()).unwrap();
+    assert_eq!(e.get_path(&[]), Some(&e));
+    assert_eq!(e.get_path(&["server", "address", "port"]).unwrap().get_text().unwrap(), "8080");
+    assert!(e.get_path(&["server", "missing", "port"]).is_none());
+    assert_eq!(e.path(&["server", "address"]).name, "address");
+
+    e.get_mut_path(&["server", "address", "port"]).unwrap().children = vec![XMLNode::Text("9090".to_owned())];
+    assert_eq!(e.path(&["server", "address", "port"]).get_text().unwrap(), "9090");
+}
+
+#[test]
+#[should_panic]
+fn test_path_panics() {
+    let e = Element::new("root");
+    e.path(&["missing"]);
+}
+
+#[test]
+fn test_attribute_order() {
+    let data = r##"<item zeta="1" alpha="2" mid="3" beta="4" />"##;
+
+    let e = Element::parse(data.as_bytes()).unwrap();
+    let keys: Vec<&str> = e.attributes.keys().map(|k| k.as_str()).collect();
+    assert_eq!(keys, vec!["zeta", "alpha", "mid", "beta"]);
+
+    let mut buf = Vec::new();
+    e.write(&mut buf).unwrap();
+    let s = String::from_utf8(buf).unwrap();
+    assert!(s.contains(r#"<item zeta="1" alpha="2" mid="3" beta="4" />"#));
+}
+
+#[test]
+fn test_parser_config() {
+    let data = r##"
+        <?xml version="1.0" encoding="utf-8" standalone="yes"?>
+        <names>
+            <!-- a comment -->
+            <?some-pi data?>
+            <name>  bob  </name>
+        </names>
+    "##;
+
+    // processing instructions are rejected by default
+    if let Err(ParseError::CannotParse) = Element::parse(data.as_bytes()) {
+        // OK
+    } else {
+        panic!("unexpected parse result");
+    }
+
+    let mut config = ParserConfig::new();
+    config.ignore_processing_instructions = true;
+    let e = Element::parse_with_config(data.as_bytes(), &config).unwrap();
+    assert_eq!(e.get_child("name").unwrap().get_text().unwrap(), "  bob  ");
+
+    config.trim_whitespace = true;
+    let e = Element::parse_with_config(data.as_bytes(), &config).unwrap();
+    assert_eq!(e.get_child("name").unwrap().get_text().unwrap(), "bob");
+
+    config.ignore_comments = false;
+    if let Err(ParseError::CannotParse) = Element::parse_with_config(data.as_bytes(), &config) {
+        // OK
+    } else {
+        panic!("unexpected parse result");
+    }
+}
+
+#[test]
+fn test_parser_config_max_depth() {
+    let data = "<a><b><c/></b></a>";
+
+    let mut config = ParserConfig::new();
+    config.max_depth = Some(3);
+    assert!(Element::parse_with_config(data.as_bytes(), &config).is_ok());
+
+    config.max_depth = Some(2);
+    if let Err(ParseError::MaxDepthExceeded) = Element::parse_with_config(data.as_bytes(), &config) {
+        // OK
+    } else {
+        panic!("unexpected parse result");
+    }
+}
+
+#[test]
+fn test_builder() {
+    let e = ElementBuilder::new("list")
+        .prefix("ns")
+        .namespace("urn:test")
+        .attr("kind", "numbers")
+        .child(Element::builder("item").attr("id", "1").text("one").build())
+        .child(Element::builder("item").attr("id", "2").text("two").build())
+        .build();
+
+    let data = r##"<ns:list xmlns:ns="urn:test" kind="numbers"><ns:item id="1">one</ns:item><ns:item id="2">two</ns:item></ns:list>"##;
+    let parsed = Element::parse(data.as_bytes()).unwrap();
+
+    assert_eq!(e.name, parsed.name);
+    assert_eq!(e.prefix, parsed.prefix);
+    assert_eq!(e.namespace, parsed.namespace);
+    assert_eq!(e.attributes, parsed.attributes);
+    assert_eq!(e.children.len(), 2);
+    let second = e.child_elements().nth(1).unwrap();
+    assert_eq!(second.attributes["id"], "2");
+    assert_eq!(second.get_text().unwrap(), "two");
+
+    let renamed = ElementBuilder::new("a").name("b").build();
+    assert_eq!(renamed, Element::new("b"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_json() {
+    let e: Element = Element::parse(File::open("tests/data/ns1.xml").unwrap()).unwrap();
+
+    let json = serde_json::to_string(&e).unwrap();
+    let e2: Element = serde_json::from_str(&json).unwrap();
+    assert_eq!(e, e2);
+
+    let minimal: Element = serde_json::from_str(r#"{"name":"foo"}"#).unwrap();
+    assert_eq!(minimal, Element::new("foo"));
+
+    assert!(serde_json::from_str::<Element>(r#"{"children":[]}"#).is_err());
+
+    let mixed = Element::parse("<p>Hello <b>world</b><![CDATA[!]]></p>".as_bytes()).unwrap();
+    let json = serde_json::to_string(&mixed).unwrap();
+    let mixed2: Element = serde_json::from_str(&json).unwrap();
+    assert_eq!(mixed, mixed2);
+
+    let mut ns = Element::new("a");
+    ns.set_attribute_ns(QName::new_ns("href", "xlink", "http://www.w3.org/1999/xlink"), "#b");
+    let json = serde_json::to_string(&ns).unwrap();
+    assert_eq!(serde_json::from_str::<Element>(&json).unwrap(), ns);
+}
+
+#[test]
+fn test_mixed_content() {
+    let data = "<p>Hello <b>world</b>!<![CDATA[ <raw> ]]></p>";
+
+    let e = Element::parse(data.as_bytes()).unwrap();
+    assert_eq!(e.children.len(), 4);
+    assert_eq!(e.children[0], XMLNode::Text("Hello ".to_owned()));
+    assert_eq!(e.children[1].as_element().unwrap().get_text().unwrap(), "world");
+    assert_eq!(e.children[2].as_text(), Some("!"));
+    assert_eq!(e.children[3].as_cdata(), Some(" <raw> "));
+    assert_eq!(e.get_text().unwrap(), "Hello ! <raw> ");
+    assert_eq!(e.child_elements().count(), 1);
+
+    let mut buf = Vec::new();
+    e.write(&mut buf).unwrap();
+    let s = String::from_utf8(buf).unwrap();
+    assert!(s.ends_with("<p>Hello <b>world</b>!<![CDATA[ <raw> ]]></p>"));
+
+    let e2 = Element::parse(s.as_bytes()).unwrap();
+    assert_eq!(e, e2);
+}
+
+#[test]
+fn test_preserve_processing_instructions() {
+    let data = r##"<?xml version="1.0" encoding="utf-8"?>
+<?xml-stylesheet type="text/xsl" href="style.xsl"?>
+<page><?php echo "hi"; ?><title>Hi</title><?empty?></page>"##;
+
+    let mut config = ParserConfig::new();
+    config.preserve_processing_instructions = true;
+
+    let (prolog, e) = Element::parse_with_prolog(data.as_bytes(), &config).unwrap();
+    assert_eq!(prolog, vec![XMLNode::ProcessingInstruction(
+        "xml-stylesheet".to_owned(),
+        Some(r#"type="text/xsl" href="style.xsl""#.to_owned()),
+    )]);
+    assert_eq!(e.children.len(), 3);
+    assert_eq!(e.children[0].as_processing_instruction(), Some(("php", Some(r#"echo "hi"; "#))));
+    assert_eq!(e.children[2].as_processing_instruction(), Some(("empty", None)));
+
+    let mut buf = Vec::new();
+    e.write_with_prolog(&prolog, &mut buf, EmitterConfig::new()).unwrap();
+    let s = String::from_utf8(buf).unwrap();
+    assert!(s.contains(r#"<?xml-stylesheet type="text/xsl" href="style.xsl"?><page><?php echo "hi"; ?>"#));
+
+    let (prolog2, e2) = Element::parse_with_prolog(s.as_bytes(), &config).unwrap();
+    assert_eq!(prolog, prolog2);
+    assert_eq!(e, e2);
+}
+
+#[test]
+fn test_preserve_comments() {
+    let data = r##"<!-- header --><config><!--  host name -->
+    <host>localhost</host><!--trailing--></config>"##;
+
+    let mut config = ParserConfig::new();
+    config.preserve_comments = true;
+
+    let (prolog, e) = Element::parse_with_prolog(data.as_bytes(), &config).unwrap();
+    assert_eq!(prolog, vec![XMLNode::Comment(" header ".to_owned())]);
+    assert_eq!(e.children[0].as_comment(), Some("  host name "));
+    assert_eq!(e.children[2].as_comment(), Some("trailing"));
+
+    let mut buf = Vec::new();
+    e.write(&mut buf).unwrap();
+    let s = String::from_utf8(buf).unwrap();
+    assert!(s.ends_with("<config><!--  host name --><host>localhost</host><!--trailing--></config>"));
+
+    let e2 = Element::parse_with_config(s.as_bytes(), &config).unwrap();
+    assert_eq!(e, e2);
+
+    // comments are still dropped by default
+    let e = Element::parse(data.as_bytes()).unwrap();
+    assert_eq!(e.children.len(), 1);
+}
+
+#[test]
+fn test_write_to_string() {
+    let e = Element::builder("foo").attr("a", "1").text("bar & baz").build();
+
+    let s = e.write_to_string().unwrap();
+    assert_eq!(s, r#"<?xml version="1.0" encoding="utf-8"?><foo a="1">bar &amp; baz</foo>"#);
+    assert_eq!(e.to_xml_string(), s);
+}
+
+#[test]
+fn test_display() {
+    let e = Element::builder("foo").child(Element::new("bar")).build();
+    assert_eq!(format!("{}", e), e.write_to_string().unwrap());
+    assert!(e.to_string().ends_with("<foo><bar /></foo>"));
+}
+
+#[test]
+fn test_from_str() {
+    let e: Element = "<foo><bar/></foo>".parse().unwrap();
+    assert_eq!(e.name, "foo");
+    assert!(e.get_child("bar").is_some());
+
+    if let Err(ParseError::MalformedXml(..)) = "<foo>".parse::<Element>() {
+        // OK
+    } else {
+        panic!("unexpected parse result");
+    }
+}
+
+#[test]
+fn test_get_child_ns() {
+    let data = r##"
+        <record xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:my="urn:my">
+            <dc:title>Dublin Core</dc:title>
+            <my:title>Mine</my:title>
+            <my:title>Mine again</my:title>
+        </record>
+    "##;
+
+    let mut e = Element::parse(data.as_bytes()).unwrap();
+    assert_eq!(e.get_child_ns("title", "http://purl.org/dc/elements/1.1/").unwrap().get_text().unwrap(), "Dublin Core");
+    assert_eq!(e.get_child_ns("title", "urn:my").unwrap().get_text().unwrap(), "Mine");
+    assert!(e.get_child_ns("title", "urn:other").is_none());
+    assert_eq!(e.get_all_children_ns("title", "urn:my").count(), 2);
+
+    e.get_mut_child_ns("title", "urn:my").unwrap().children.clear();
+    assert_eq!(e.get_child_ns("title", "urn:my").unwrap().get_text(), None);
+}
+
+#[test]
+fn test_qualified_name() {
+    let e = Element::parse(File::open("tests/data/ns1.xml").unwrap()).unwrap();
+    assert_eq!(e.qualified_name(), "root");
+    assert_eq!(e.get_child("table").unwrap().qualified_name(), "h:table");
+
+    let plain = Element::new("title");
+    assert_eq!(plain.qualified_name(), "title");
+
+    let prefixed = Element::builder("title").prefix("dc").build();
+    assert_eq!(prefixed.qualified_name(), "dc:title");
+}
+
+#[test]
+fn test_cdata_rw() {
+    let data = r##"<script><![CDATA[if (a < b && c > d) { run(); }]]></script>"##;
+
+    let e = Element::parse(data.as_bytes()).unwrap();
+    assert_eq!(e.children, vec![XMLNode::CData("if (a < b && c > d) { run(); }".to_owned())]);
+
+    let s = e.write_to_string().unwrap();
+    assert!(s.ends_with(data));
+    assert_eq!(Element::parse(s.as_bytes()).unwrap(), e);
+}
+
+#[test]
+fn test_attribute_accessors() {
+    let mut e: Element = r#"<item a="1" b="2" c="3" />"#.parse().unwrap();
+    assert_eq!(e.get_attribute("b"), Some("2"));
+    assert_eq!(e.get_attribute("z"), None);
+    assert!(e.has_attribute("a"));
+    assert!(!e.has_attribute("z"));
+
+    e.set_attribute("b", "two");
+    e.set_attribute("d".to_owned(), "4");
+    assert_eq!(e.get_attribute("b"), Some("two"));
+
+    assert_eq!(e.remove_attribute("a"), Some("1".to_owned()));
+    assert_eq!(e.remove_attribute("a"), None);
+    let keys: Vec<&str> = e.attributes.keys().map(|k| k.as_str()).collect();
+    assert_eq!(keys, vec!["b", "c", "d"]);
+}
+
+#[test]
+fn test_get_attr_as() {
+    let e: Element = r#"<item count="42" enabled="true" ratio="nope" />"#.parse().unwrap();
+    assert_eq!(e.get_attr_as::<u32>("count"), Some(Ok(42)));
+    assert_eq!(e.get_attr_as::<bool>("enabled"), Some(Ok(true)));
+    assert!(e.get_attr_as::<f64>("ratio").unwrap().is_err());
+    assert!(e.get_attr_as::<u32>("missing").is_none());
+
+    assert_eq!(e.required_attr_as::<u32>("count"), Ok(42));
+    assert_eq!(e.required_attr_as::<u32>("missing"), Err(AttrError::Missing("missing".to_owned())));
+    if let Err(AttrError::Invalid(name, _)) = e.required_attr_as::<f64>("ratio") {
+        assert_eq!(name, "ratio");
+    } else {
+        panic!("unexpected result");
+    }
+}
+
+#[test]
+fn test_children_iter() {
+    let mut e: Element = "<list>text<a/><!-- c --><b/></list>".parse().unwrap();
+    let names: Vec<&str> = e.children().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b"]);
+
+    for child in e.children_mut() {
+        child.set_attribute("visited", "1");
+    }
+    assert!(e.children().all(|c| c.has_attribute("visited")));
+    assert_eq!(e.children.len(), 3);
+}
+
+#[test]
+fn test_descendants() {
+    let mut e: Element = "<a><b><c/>text<d/></b><e><f/></e></a>".parse().unwrap();
+    let names: Vec<&str> = e.descendants().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["b", "c", "d", "e", "f"]);
+    assert_eq!(Element::new("a").descendants().count(), 0);
+
+    let mut visited = Vec::new();
+    e.for_each_descendant_mut(|d| {
+        visited.push(d.name.clone());
+        d.name = d.name.to_uppercase();
+    });
+    assert_eq!(visited, vec!["b", "c", "d", "e", "f"]);
+    let names: Vec<&str> = e.descendants().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["B", "C", "D", "E", "F"]);
+    assert_eq!(e.name, "a");
+}
+
+#[test]
+fn test_find_descendant() {
+    let mut e: Element = r#"
+        <root>
+            <section id="main"><p id="first"/></section>
+            <section><div id="main"/><p/></section>
+        </root>
+    "#.parse().unwrap();
+
+    let main = e.find_descendant(|d| d.get_attribute("id") == Some("main")).unwrap();
+    assert_eq!(main.name, "section");
+    assert!(e.find_descendant(|d| d.name == "missing").is_none());
+
+    let mains = e.find_all_descendants(|d| d.get_attribute("id") == Some("main"));
+    let names: Vec<&str> = mains.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["section", "div"]);
+    assert_eq!(e.find_all_descendants(|d| d.name == "p").len(), 2);
+
+    e.find_descendant_mut(|d| d.name == "div").unwrap().set_attribute("id", "other");
+    assert_eq!(e.find_all_descendants(|d| d.get_attribute("id") == Some("main")).len(), 1);
+}
+
+#[test]
+fn test_retain_children() {
+    let mut e: Element = r#"<root>text<a keep="1"/><b/><c keep="1"/></root>"#.parse().unwrap();
+
+    let kept: Vec<&str> = e.filter_children(|c| c.has_attribute("keep"))
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    assert_eq!(kept, vec!["a", "c"]);
+
+    e.retain_children(|c| c.has_attribute("keep"));
+    let names: Vec<&str> = e.children().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "c"]);
+    assert_eq!(e.get_text().unwrap(), "text");
+}
+
+#[test]
+fn test_sort_children() {
+    let mut e: Element = r#"<root><c n="1"/>text<a n="3"/><b n="2"/></root>"#.parse().unwrap();
+
+    e.sort_children_by_key(|c| c.name.clone());
+    let names: Vec<&str> = e.children().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b", "c"]);
+    assert_eq!(e.children[1], XMLNode::Text("text".to_owned()));
+
+    e.sort_children_by(|x, y| y.attributes["n"].cmp(&x.attributes["n"]));
+    let names: Vec<&str> = e.children().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b", "c"]);
+
+    e.sort_children_by(|x, y| x.attributes["n"].cmp(&y.attributes["n"]));
+    let names: Vec<&str> = e.children().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["c", "b", "a"]);
+}
+
+#[test]
+fn test_hash() {
+    use std::collections::HashSet;
+
+    let a: Element = r#"<item x="1" y="2"><child>text</child></item>"#.parse().unwrap();
+    let b: Element = r#"<item y="2" x="1"><child>text</child></item>"#.parse().unwrap();
+    let c: Element = r#"<item x="1" y="2"><child>other</child></item>"#.parse().unwrap();
+    assert_eq!(a, b);
+
+    let mut set = HashSet::new();
+    set.insert(a);
+    assert!(set.contains(&b));
+    assert!(!set.contains(&c));
+    set.insert(b);
+    set.insert(c);
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_default() {
+    let e = Element::default();
+    assert_eq!(e, Element::new(""));
+    assert!(e.attributes.is_empty());
+    assert!(e.children.is_empty());
+    assert_eq!(e.namespaces, None);
+}
+
+#[test]
+fn test_insert_child() {
+    let mut e: Element = "<root><b/><d/></root>".parse().unwrap();
+    e.insert_child(1, Element::new("c"));
+    e.prepend_child(Element::new("a"));
+    e.insert_child(4, Element::new("e"));
+
+    let names: Vec<&str> = e.children().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b", "c", "d", "e"]);
+}
+
+#[test]
+#[should_panic]
+fn test_insert_child_out_of_bounds() {
+    let mut e = Element::new("root");
+    e.insert_child(1, Element::new("a"));
+}
+
+#[test]
+fn test_index_based_mutation() {
+    let mut e: Element = "<root><a/>text<b/><c/></root>".parse().unwrap();
+    assert_eq!(e.child_position("a"), Some(0));
+    assert_eq!(e.child_position("b"), Some(2));
+    assert_eq!(e.child_position("missing"), None);
+
+    e.swap_children(0, 3);
+    let names: Vec<&str> = e.children().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["c", "b", "a"]);
+
+    let i = e.child_position("b").unwrap();
+    let removed = e.remove_child_at(i);
+    assert_eq!(removed.as_element().unwrap().name, "b");
+    assert_eq!(e.remove_child_at(1), XMLNode::Text("text".to_owned()));
+    assert_eq!(e.children.len(), 2);
+}
+
+#[test]
+fn test_get_or_create_child() {
+    let mut e: Element = r#"<config><server host="a"/></config>"#.parse().unwrap();
+
+    e.get_or_create_child("server").set_attribute("port", "80");
+    assert_eq!(e.children.len(), 1);
+    assert_eq!(e.get_child("server").unwrap().get_attribute("port"), Some("80"));
+
+    e.get_or_create_child("client").set_attribute("name", "b");
+    assert_eq!(e.children.len(), 2);
+    assert_eq!(e.children().last().unwrap().name, "client");
+
+    e.get_or_create_child("db").get_or_create_child("user");
+    assert!(e.get_path(&["db", "user"]).is_some());
+}
+
+#[test]
+fn test_extend_children() {
+    let mut e = Element::new("root");
+    e.extend_children(vec![Element::new("a"), Element::new("b")]);
+    e.extend_children_from(&[Element::new("c")]);
+
+    let names: Vec<&str> = e.children().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b", "c"]);
+
+    let drained: Vec<XMLNode> = e.drain_children().collect();
+    assert_eq!(drained.len(), 3);
+    assert!(e.children.is_empty());
+}
+
+#[test]
+fn test_move_children() {
+    let mut a: Element = "<a><x/>text</a>".parse().unwrap();
+    let mut b: Element = "<b><y/></b>".parse().unwrap();
+
+    a.move_children_to(&mut b);
+    assert!(a.children.is_empty());
+    assert_eq!(b.children.len(), 3);
+    assert_eq!(b.children[1].as_element().unwrap().name, "x");
+
+    let taken = b.take_children();
+    assert_eq!(taken.len(), 3);
+    assert!(b.children.is_empty());
+}
+
+#[test]
+fn test_write_fragment() {
+    let e: Element = r#"<foo a="1"><bar/></foo>"#.parse().unwrap();
+
+    let mut buf = Vec::new();
+    e.write_fragment(&mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), r#"<foo a="1"><bar /></foo>"#);
+}
+
+#[test]
+fn test_parse_fragment() {
+    let e = Element::parse_fragment("<foo/>".as_bytes()).unwrap();
+    assert_eq!(e, Element::new("foo"));
+
+    let e = Element::parse_fragment("\n  <foo a=\"1\"><bar/></foo>\n".as_bytes()).unwrap();
+    assert_eq!(e.get_attribute("a"), Some("1"));
+    assert!(e.get_child("bar").is_some());
+
+    assert!(Element::parse_fragment("just text".as_bytes()).is_err());
+}
+
+#[test]
+fn test_parse_with_reader_config() {
+    let data = "<root><a>  text  </a><b>&custom;</b></root>";
+
+    let reader_config = xml::reader::ParserConfig::new()
+        .trim_whitespace(true)
+        .add_entity("custom", "expanded");
+    let e = Element::parse_with_reader_config(data.as_bytes(), reader_config).unwrap();
+    assert_eq!(e.get_child("a").unwrap().get_text().unwrap(), "text");
+    assert_eq!(e.get_child("b").unwrap().get_text().unwrap(), "expanded");
+
+    assert!(Element::parse(data.as_bytes()).is_err());
+}
+
+#[test]
+fn test_parse_all() {
+    let data = r##"
+        <event id="1"><msg>start</msg></event>
+        <!-- separator -->
+        <event id="2"/>
+        <event id="3"/>
+    "##;
+
+    let events = Element::parse_all(data.as_bytes()).unwrap();
+    let ids: Vec<&str> = events.iter().map(|e| e.get_attribute("id").unwrap()).collect();
+    assert_eq!(ids, vec!["1", "2", "3"]);
+
+    let mut iter = Element::parse_iter(data.as_bytes());
+    assert_eq!(iter.next().unwrap().unwrap().get_attribute("id"), Some("1"));
+    assert_eq!(iter.count(), 2);
+
+    // a stream without any root element is malformed
+    if let Err(ParseError::MalformedXml(..)) = Element::parse_all("".as_bytes()) {
+        // OK
+    } else {
+        panic!("unexpected parse result");
+    }
+
+    let results: Vec<_> = Element::parse_iter("<a/><b></c>".as_bytes()).collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+}
+
+#[test]
+fn test_xml_size_estimate() {
+    for file in &["tests/data/01.xml", "tests/data/ns1.xml", "tests/data/ns2.xml", "tests/data/rw.xml"] {
+        let mut config = ParserConfig::new();
+        config.preserve_comments = true;
+        config.preserve_processing_instructions = true;
+        let e = Element::parse_with_config(File::open(file).unwrap(), &config).unwrap();
+        let actual = e.write_to_string().unwrap().len();
+        assert!(e.xml_size_estimate() >= actual, "{}: {} < {}", file, e.xml_size_estimate(), actual);
+    }
+
+    let e = Element::builder("a").attr("q", "\"&'<>").text("<&>").build();
+    assert!(e.xml_size_estimate() >= e.write_to_string().unwrap().len());
+
+    let e = Element::builder("a").attr("q", "\t\n\r".repeat(50)).build();
+    let actual = e.write_to_string().unwrap().len();
+    assert!(e.xml_size_estimate() >= actual, "{} < {}", e.xml_size_estimate(), actual);
+
+    // children built without a namespace get `xmlns=""` under a default namespace
+    let mut e = Element::parse(r#"<foo xmlns="urn:x"/>"#.as_bytes()).unwrap();
+    for _ in 0..100 {
+        e.children.push(XMLNode::Element(Element::new("p")));
+    }
+    let actual = e.write_to_string().unwrap().len();
+    assert!(e.xml_size_estimate() >= actual, "{} < {}", e.xml_size_estimate(), actual);
+}
+
+#[test]
+fn test_tree_statistics() {
+    let e: Element = "<a><b><c/>text</b><d/></a>".parse().unwrap();
+    assert_eq!(e.max_depth(), 3);
+    assert_eq!(e.node_count(), 4);
+
+    let leaf = Element::new("leaf");
+    assert_eq!(leaf.max_depth(), 1);
+    assert_eq!(leaf.node_count(), 1);
+}
+
+#[test]
+fn test_ord() {
+    use std::collections::BTreeSet;
+
+    let a: Element = r#"<a x="1" y="2"/>"#.parse().unwrap();
+    let a2: Element = r#"<a y="2" x="1"/>"#.parse().unwrap();
+    let a3: Element = r#"<a x="1" y="3"/>"#.parse().unwrap();
+    let b: Element = "<b/>".parse().unwrap();
+    let b_child: Element = "<b><c/></b>".parse().unwrap();
+
+    assert_eq!(a.cmp(&a2), std::cmp::Ordering::Equal);
+    assert!(a < a3);
+    assert!(a3 < b);
+    assert!(b < b_child);
+
+    let set: BTreeSet<Element> = vec![b_child.clone(), a.clone(), a2, b.clone()].into_iter().collect();
+    assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![a, b, b_child]);
+}
+
+#[test]
+fn test_write_prefix() {
+    let data = r#"<dc:title xmlns:dc="http://purl.org/dc/elements/1.1/">foo</dc:title>"#;
+    let e: Element = data.parse().unwrap();
+    let s = e.write_to_string().unwrap();
+    assert!(s.ends_with(data));
+    assert_eq!(Element::parse(s.as_bytes()).unwrap(), e);
+
+    // the mapping for the element's own prefix is declared even if `namespaces` lacks it
+    let e = Element::builder("title").prefix("dc").namespace("urn:dc").build();
+    let e2 = Element::parse(e.write_to_string().unwrap().as_bytes()).unwrap();
+    assert_eq!(e2.qualified_name(), "dc:title");
+    assert_eq!(e2.namespace.as_deref(), Some("urn:dc"));
+
+    let e = Element::builder("root")
+        .namespace("urn:default")
+        .child(Element::builder("child").namespace("urn:default").build())
+        .build();
+    let s = e.write_to_string().unwrap();
+    assert!(s.ends_with(r#"<root xmlns="urn:default"><child /></root>"#));
+    let e2 = Element::parse(s.as_bytes()).unwrap();
+    assert_eq!(e2.get_child("child").unwrap().namespace.as_deref(), Some("urn:default"));
+}
+
+#[test]
+fn test_attributes_ns() {
+    let data = r##"<svg xmlns:xlink="http://www.w3.org/1999/xlink"><use id="b" xlink:href="#a" /></svg>"##;
+    let e: Element = data.parse().unwrap();
+    let u = e.get_child("use").unwrap();
+    assert_eq!(u.get_attribute("id"), Some("b"));
+    assert_eq!(u.get_attribute("href"), None);
+    assert_eq!(u.get_attribute_ns("href", "http://www.w3.org/1999/xlink"), Some("#a"));
+    assert_eq!(u.attributes_ns.keys().next().unwrap().to_string(), "xlink:href");
+
+    let s = e.write_to_string().unwrap();
+    assert!(s.ends_with(data));
+    assert_eq!(Element::parse(s.as_bytes()).unwrap(), e);
+
+    // the mapping for an attribute's prefix is declared even if `namespaces` lacks it
+    let mut e = Element::new("a");
+    e.set_attribute_ns(QName::new_ns("lang", "x", "urn:x"), "en");
+    let e2 = Element::parse(e.write_to_string().unwrap().as_bytes()).unwrap();
+    assert_eq!(e2.get_attribute_ns("lang", "urn:x"), Some("en"));
+    assert!(e.xml_size_estimate() >= e.write_to_string().unwrap().len());
+
+    assert_eq!(e.remove_attribute_ns("lang", "urn:x"), Some("en".to_owned()));
+    assert!(e.attributes_ns.is_empty());
+}
+
+#[test]
+fn test_strip_namespaces() {
+    let e: Element = Element::parse(File::open("tests/data/ns1.xml").unwrap()).unwrap();
+    let stripped = e.without_namespaces();
+    assert_ne!(stripped, e);
+    assert!(stripped.namespaces.is_none());
+    for d in std::iter::once(&stripped).chain(stripped.descendants()) {
+        assert!(d.prefix.is_none() && d.namespace.is_none() && d.namespaces.is_none());
+    }
+
+    let mut e: Element = r#"<a xmlns:x="urn:x" x:id="1" y="2"><x:b x:y="3" y="4" /></a>"#.parse().unwrap();
+    e.strip_namespaces();
+    let mut out = Vec::new();
+    e.write_fragment(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), r#"<a y="2" id="1"><b y="4" /></a>"#);
+}
+
+#[test]
+fn test_namespace_for_prefix() {
+    let e: Element = r#"<a xmlns="urn:d" xmlns:x="urn:x"><b xmlns:y="urn:y" /></a>"#.parse().unwrap();
+    let b = e.get_child("b").unwrap();
+    assert_eq!(b.namespace_for_prefix("x"), Some("urn:x"));
+    assert_eq!(b.namespace_for_prefix("y"), Some("urn:y"));
+    assert_eq!(b.namespace_for_prefix(""), Some("urn:d"));
+    assert_eq!(e.namespace_for_prefix("y"), None);
+    assert_eq!(Element::new("c").namespace_for_prefix("x"), None);
+}
+
+#[test]
+fn test_normalize_whitespace() {
+    let e: Element = "<a>  hello \n\t world  <b> x  </b> <![CDATA[  y  ]]></a>".parse().unwrap();
+    let n = e.with_normalized_whitespace();
+    assert_eq!(n.get_text().unwrap(), "hello world  y  ");
+    assert_eq!(n.get_child("b").unwrap().get_text().unwrap(), "x");
+    assert_eq!(n.children.len(), 3);
+    assert_ne!(n, e);
+}
+
+#[test]
+fn test_get_text_or_empty() {
+    let e: Element = "<a>x<b/>y</a>".parse().unwrap();
+    assert_eq!(e.get_text_or_empty(), "xy");
+    assert_eq!(e.get_child("b").unwrap().get_text_or_empty(), "");
+}
+
+#[test]
+fn test_set_text() {
+    let mut e: Element = "<a>x<b/>y<![CDATA[z]]></a>".parse().unwrap();
+    e.set_text("new");
+    assert_eq!(e.get_text().unwrap(), "new");
+    assert!(e.children[0].as_text().is_some());
+    assert!(e.children[1].as_element().is_some());
+
+    e.clear_text();
+    assert_eq!(e.get_text(), None);
+    assert_eq!(e.children.len(), 1);
+
+    let mut e = Element::new("c");
+    e.set_text(String::from("d"));
+    assert_eq!(e.children, vec![XMLNode::Text("d".to_owned())]);
+}
+
+#[test]
+fn test_get_child_text() {
+    let e: Element = "<config><host>localhost</host><port/></config>".parse().unwrap();
+    assert_eq!(e.get_child_text("host").unwrap(), "localhost");
+    assert_eq!(e.get_child_text("port"), None);
+    assert_eq!(e.get_child_text("user"), None);
+    assert_eq!(e.get_child_text_or("port", "80"), "80");
+    assert_eq!(e.get_child_text_or("host", "127.0.0.1"), "localhost");
+}
+
+#[test]
+fn test_visitor() {
+    struct Recorder(Vec<String>);
+
+    impl Visitor for Recorder {
+        fn visit_element(&mut self, elem: &Element) {
+            self.0.push(elem.name.clone());
+        }
+
+        fn leave_element(&mut self, elem: &Element) {
+            self.0.push(format!("/{}", elem.name));
+        }
+    }
+
+    struct Upper;
+
+    impl MutVisitor for Upper {
+        fn visit_element(&mut self, elem: &mut Element) {
+            elem.name = elem.name.to_uppercase();
+        }
+    }
+
+    let mut e: Element = "<a><b><c/></b>text<d/></a>".parse().unwrap();
+    let mut r = Recorder(Vec::new());
+    e.accept(&mut r);
+    assert_eq!(r.0, vec!["a", "b", "c", "/c", "/b", "d", "/d", "/a"]);
+
+    e.accept_mut(&mut Upper);
+    assert_eq!(e, "<A><B><C/></B>text<D/></A>".parse().unwrap());
+
+    e.accept(&mut DefaultVisitor);
+    e.accept_mut(&mut DefaultVisitor);
+}
+
+#[test]
+fn test_map_tree() {
+    let e: Element = r#"<a x="1"><b x="2">text</b><c/></a>"#.parse().unwrap();
+    let mapped = e.map_tree(|e| {
+        let mut e = e.clone();
+        e.name = format!("{}{}", e.name, e.children.len());
+        e.remove_attribute("x");
+        e
+    });
+    assert_eq!(mapped, "<a2><b1>text</b1><c0/></a2>".parse().unwrap());
+    assert_eq!(e.map_tree(Element::clone), e);
+
+    // bottom-up: the children have already been visited when the parent is
+    let mut e = e;
+    e.in_place_map_tree(|e| {
+        let n: usize = e
+            .children()
+            .map(|c| c.get_attr_as::<usize>("n").unwrap().unwrap() + 1)
+            .sum();
+        e.set_attribute("n", n.to_string());
+    });
+    assert_eq!(e.get_attribute("n"), Some("2"));
+    assert_eq!(e.get_child("c").unwrap().get_attribute("n"), Some("0"));
+}
+
+#[test]
+fn test_fold_tree() {
+    let e: Element = r#"<a n="1"><b n="2"><a n="3"/></b><c/></a>"#.parse().unwrap();
+    assert_eq!(e.fold_tree(0, |count, _| count + 1), 4);
+    let sum = e.fold_tree(0, |sum, e| sum + e.get_attr_as::<u32>("n").map_or(0, Result::unwrap));
+    assert_eq!(sum, 6);
+    let names = e.fold_tree(Vec::new(), |mut names, e| {
+        if !names.contains(&e.name) {
+            names.push(e.name.clone());
+        }
+        names
+    });
+    assert_eq!(names, vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_diff() {
+    use xmltree::diff::{diff, Difference};
+
+    let a: Element = r#"<a x="1" y="2"><b>hi</b><c/><d/></a>"#.parse().unwrap();
+    assert!(diff(&a, &a.clone()).is_empty());
+
+    let b: Element = r#"<a x="3" z="4"><b>ho</b><e/></a>"#.parse().unwrap();
+    let path = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let d = diff(&a, &b);
+    assert_eq!(
+        d,
+        vec![
+            Difference::AttributeValueMismatch {
+                path: path(&["a"]),
+                name: "x".to_owned(),
+                expected: "1".to_owned(),
+                actual: "3".to_owned(),
+            },
+            Difference::AttributeMissing {
+                path: path(&["a"]),
+                name: "y".to_owned(),
+            },
+            Difference::ExtraAttribute {
+                path: path(&["a"]),
+                name: "z".to_owned(),
+            },
+            Difference::ChildCountMismatch {
+                path: path(&["a"]),
+                expected: 3,
+                actual: 2,
+            },
+            Difference::TextMismatch {
+                path: path(&["a", "b"]),
+                expected: "hi".to_owned(),
+                actual: "ho".to_owned(),
+            },
+            Difference::ElementNameMismatch {
+                path: path(&["a", "c"]),
+                expected: "c".to_owned(),
+                actual: "e".to_owned(),
+            },
+            Difference::MissingChild {
+                path: path(&["a"]),
+                name: "d".to_owned(),
+            },
+        ]
+    );
+    assert_eq!(d[4].to_string(), r#"/a/b: expected text "hi", found "ho""#);
+
+    let d = diff(&b, &a);
+    assert_eq!(d.last().unwrap().path(), &path(&["a"])[..]);
+    assert!(matches!(d.last(), Some(Difference::ExtraChild { element, .. }) if element.name == "d"));
+}
+
+#[test]
+fn test_pretty_print() {
+    let e: Element = "<a><b>text</b><c><d/></c></a>".parse().unwrap();
+    let s = e.to_pretty_string(DEFAULT_INDENT);
+    assert!(s.ends_with("\n<a>\n  <b>text</b>\n  <c>\n    <d />\n  </c>\n</a>"), "{}", s);
+    assert_eq!(Element::parse(s.as_bytes()).unwrap(), e);
+
+    let mut out = Vec::new();
+    e.pretty_print(&mut out, "\t").unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("\n\t\t<d />"));
+}
+
+#[test]
+fn test_to_compact_string() {
+    let e: Element = "<a>\n  <b x=\"1\"></b>\n  <c>text</c>\n</a>".parse().unwrap();
+    assert_eq!(e.to_compact_string(), r#"<a><b x="1" /><c>text</c></a>"#);
+}
+
+#[test]
+fn test_parse_error_source() {
+    use std::error::Error;
+
+    let err = Element::parse("<a>".as_bytes()).unwrap_err();
+    assert!(err.source().is_some());
+    assert!(ParseError::CannotParse.source().is_none());
+
+    fn parse(s: &str) -> Result<Element, Box<dyn Error>> {
+        Ok(Element::parse(s.as_bytes())?)
+    }
+    assert!(parse("<a/>").is_ok());
+    assert!(parse("<a>").unwrap_err().to_string().starts_with("Malformed XML"));
+}
+
+#[test]
+fn test_parse_error_from_reader_error() {
+    fn root_event(s: &str) -> Result<xml::reader::XmlEvent, ParseError> {
+        let mut reader = xml::reader::EventReader::new(s.as_bytes());
+        reader.next()?;
+        Ok(reader.next()?)
+    }
+    assert!(root_event("<a/>").is_ok());
+    match root_event("<<") {
+        Err(ParseError::MalformedXml(_)) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_error_position() {
+    let err = Element::parse("<a>\n  <b></c>\n</a>".as_bytes()).unwrap_err();
+    assert_eq!(err.position().map(|(line, _)| line), Some(2));
+    assert_eq!(ParseError::MaxDepthExceeded.position(), None);
+}
+
+#[test]
+fn test_parse_lenient() {
+    let data = r#"<?xml version="1.0"?><?mso-application progid="Word.Document"?><a><?pi data?><!-- c --><b/></a>"#;
+    assert!(Element::parse(data.as_bytes()).is_err());
+    let e = Element::parse_lenient(data.as_bytes()).unwrap();
+    assert_eq!(e.children.len(), 1);
+    assert!(e.get_child("b").is_some());
+}
+
+#[test]
+fn test_is_empty() {
+    let e: Element = r#"<a><b/><c x="1"/><d>text</d></a>"#.parse().unwrap();
+    assert!(e.has_children() && !e.has_text() && !e.is_empty());
+    let b = e.get_child("b").unwrap();
+    assert!(!b.has_children() && !b.has_text() && b.is_empty());
+    assert!(!e.get_child("c").unwrap().is_empty());
+    let d = e.get_child("d").unwrap();
+    assert!(!d.has_children() && d.has_text() && !d.is_empty());
+    assert!(Element::new("e").is_empty());
+}
+
+#[test]
+fn test_clone_without_children() {
+    let e: Element = r#"<x:a xmlns:x="urn:x" id="1">text<b/></x:a>"#.parse().unwrap();
+    let c = e.clone_without_children();
+    assert!(c.children.is_empty());
+    assert_eq!(c.qualified_name(), "x:a");
+    assert_eq!(c.namespace, e.namespace);
+    assert_eq!(c.attributes, e.attributes);
+}
+
+#[test]
+fn test_rename() {
+    let mut e = Element::new("a");
+    e.rename("item").set_attribute("id", "1");
+    assert_eq!(e.name, "item");
+    assert_eq!(e.get_attribute("id"), Some("1"));
+
+    let e = e.with_name(String::from("entry"));
+    assert_eq!(e.name, "entry");
+}
+
+#[test]
+fn test_write_canonical() {
+    let data = r#"<?xml version="1.0"?>
+<doc xmlns:b="urn:b" xmlns:a="urn:a" z="1" b:y="2" a:x="3" c="&quot;"><e1/><a:e2 attr="a&#10;b">x &amp; y &gt; z<![CDATA[<cdata>]]><!-- comment --></a:e2><e3 xmlns:a="urn:a"/></doc>"#;
+    let e: Element = Element::parse_with_config(
+        data.as_bytes(),
+        &ParserConfig {
+            preserve_comments: true,
+            ..ParserConfig::new()
+        },
+    )
+    .unwrap();
+
+    let mut out = Vec::new();
+    e.write_canonical(&mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        concat!(
+            r#"<doc xmlns:a="urn:a" xmlns:b="urn:b" c="&quot;" z="1" a:x="3" b:y="2">"#,
+            r#"<e1></e1><a:e2 attr="a&#xA;b">x &amp; y &gt; z&lt;cdata&gt;</a:e2><e3></e3></doc>"#
+        )
+    );
+
+    // namespaces of programmatically built elements are declared where they are first used
+    let e = Element::builder("root")
+        .child(Element::builder("child").prefix("p").namespace("urn:p").build())
+        .build();
+    let mut out = Vec::new();
+    e.write_canonical(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), r#"<root><p:child xmlns:p="urn:p"></p:child></root>"#);
+
+    let e: Element = r#"<a xmlns="urn:d"><b xmlns=""><c/></b></a>"#.parse().unwrap();
+    let mut out = Vec::new();
+    e.write_canonical(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), r#"<a xmlns="urn:d"><b xmlns=""><c></c></b></a>"#);
+
+    // in-scope namespaces are declared even when unused, and not repeated by descendants
+    let e: Element = r#"<root xmlns:p="urn:p"><child><p:x/></child></root>"#.parse().unwrap();
+    let mut out = Vec::new();
+    e.write_canonical(&mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        r#"<root xmlns:p="urn:p"><child><p:x></p:x></child></root>"#
+    );
+    let e: Element = r#"<root xmlns:p="urn:p"/>"#.parse().unwrap();
+    let mut out = Vec::new();
+    e.write_canonical(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), r#"<root xmlns:p="urn:p"></root>"#);
+}
+
+#[test]
+fn test_write_children_only() {
+    let e: Element = r#"<wrapper xmlns:x="urn:x">text<a/><x:b c="d"/></wrapper>"#.parse().unwrap();
+    let mut out = Vec::new();
+    e.write_children_only(&mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        r#"text<a xmlns:x="urn:x" /><x:b xmlns:x="urn:x" c="d" />"#
+    );
+}
+
+#[test]
+fn test_parse_bom() {
+    let data = b"\xEF\xBB\xBF<?xml version=\"1.0\" encoding=\"utf-8\"?><a>text</a>";
+    let e = Element::parse(&data[..]).unwrap();
+    assert_eq!(e.get_text().unwrap(), "text");
+    let e = Element::parse(&b"\xEF\xBB\xBF<a/>"[..]).unwrap();
+    assert_eq!(e.name, "a");
+}
+
+#[test]
+fn test_escape_xml() {
+    use std::borrow::Cow;
+    use xmltree::util::{escape_xml, unescape_xml};
+
+    assert!(matches!(escape_xml("plain"), Cow::Borrowed("plain")));
+    assert_eq!(escape_xml(r#"<a href="x">Tom & 'Jerry'</a>"#), "&lt;a href=&quot;x&quot;&gt;Tom &amp; &apos;Jerry&apos;&lt;/a&gt;");
+
+    assert!(matches!(unescape_xml("plain"), Cow::Borrowed("plain")));
+    assert_eq!(unescape_xml("&lt;b&gt; &amp;amp; &quot;&apos;"), "<b> &amp; \"'");
+    assert_eq!(unescape_xml("&#65;&#x42;&#x1F600;"), "AB\u{1F600}");
+    assert_eq!(unescape_xml("a & b &unknown; &#xZZ; &#0; &#+1; &"), "a & b &unknown; &#xZZ; &#0; &#+1; &");
+
+    let s = "<&>\"' mixed é";
+    assert_eq!(unescape_xml(&escape_xml(s)), s);
+}
+
+#[test]
+fn test_is_valid_xml_name() {
+    use xmltree::util::{assert_valid_xml_name, is_valid_xml_name};
+
+    for name in &["a", "_a", "x:id", "a-b.c1", "élément", "名前"] {
+        assert!(is_valid_xml_name(name), "{}", name);
+    }
+    for name in &["", "1a", "-a", ".a", "a b", "a<b", "a&", "a\"b"] {
+        assert!(!is_valid_xml_name(name), "{}", name);
+    }
+
+    assert_valid_xml_name("ok");
+    if cfg!(debug_assertions) {
+        assert!(std::panic::catch_unwind(|| assert_valid_xml_name("not ok")).is_err());
+    }
+}
+
+#[test]
+fn test_debug_xml() {
+    let e: Element = r#"<a x="1"><b>text</b><c/></a>"#.parse().unwrap();
+    assert_eq!(format!("{:?}", e.debug_xml()), r#"<a x="1"><b>text</b><c /></a>"#);
+    assert_eq!(format!("{:#?}", DebugXml(&e)), "<a x=\"1\">\n  <b>text</b>\n  <c />\n</a>");
+    assert!(format!("{:?}", e).starts_with("Element {"));
+}
+
+#[test]
+fn test_try_from_str() {
+    use std::convert::{TryFrom, TryInto};
+
+    let e = Element::try_from("<a><b/></a>").unwrap();
+    assert_eq!(e, Element::try_from(String::from("<a><b/></a>")).unwrap());
+    assert!(Element::try_from("<a>").is_err());
+
+    fn convert<S: TryInto<Element>>(s: S) -> Option<Element> {
+        s.try_into().ok()
+    }
+    assert_eq!(convert("<a><b/></a>"), Some(e));
+}
+
+#[test]
+fn test_string_from_element() {
+    let e: Element = "<a><b/></a>".parse().unwrap();
+    let s = String::from(&e);
+    assert_eq!(s, e.to_xml_string());
+
+    fn takes_into<S: Into<String>>(s: S) -> String {
+        s.into()
+    }
+    assert_eq!(takes_into(e), s);
+}
+
+#[test]
+fn test_parse_iter_is_lazy() {
+    use std::io::{self, Read};
+
+    // a stream that delivers one element and then fails, like a dropped connection
+    struct Stream(io::Cursor<&'static [u8]>);
+
+    impl Read for Stream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.read(buf)? {
+                0 => Err(io::Error::new(io::ErrorKind::ConnectionReset, "gone")),
+                n => Ok(n),
+            }
+        }
+    }
+
+    let mut iter = Element::parse_iter(Stream(io::Cursor::new(b"<stream:a xmlns:stream=\"urn:s\">1</stream:a>")));
+    let first = iter.next().unwrap().unwrap();
+    assert_eq!(first.get_text().unwrap(), "1");
+    assert!(iter.next().unwrap().is_err());
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn test_nth_child() {
+    let mut e: Element = "<a>text<b/><!-- c --><c/>more<d/>tail</a>".parse().unwrap();
+    assert_eq!(e.nth_child(0).unwrap().name, "b");
+    assert_eq!(e.nth_child(2).unwrap().name, "d");
+    assert!(e.nth_child(3).is_none());
+    assert_eq!(e.first_child().unwrap().name, "b");
+    assert_eq!(e.last_child().unwrap().name, "d");
+
+    e.nth_child_mut(1).unwrap().name = "x".to_owned();
+    assert!(e.get_child("x").is_some());
+    assert!(Element::new("e").first_child().is_none());
+    assert!(Element::new("e").last_child().is_none());
+}
+
+#[test]
+fn test_take_first_last_child() {
+    let mut e: Element = "<a>x<b/><c/><d/>y</a>".parse().unwrap();
+    assert_eq!(e.take_first_child().unwrap().name, "b");
+    assert_eq!(e.take_last_child().unwrap().name, "d");
+    assert_eq!(e.take_last_child().unwrap().name, "c");
+    assert!(e.take_first_child().is_none());
+    assert!(e.take_last_child().is_none());
+    assert_eq!(e.get_text().unwrap(), "xy");
+}
+
+#[test]
+fn test_reverse_children() {
+    let mut e: Element = "<a><b/>x<c/><d/>y<e/></a>".parse().unwrap();
+    e.reverse_children().set_attribute("reversed", "1");
+    assert_eq!(e, r#"<a reversed="1"><e/>x<d/><c/>y<b/></a>"#.parse().unwrap());
+
+    let mut e: Element = "<a><b/><c/><d/></a>".parse().unwrap();
+    e.reverse_children();
+    assert_eq!(e, "<a><d/><c/><b/></a>".parse().unwrap());
+}
+
+#[test]
+fn test_dedup_children() {
+    let mut e: Element = r#"<a><b/><b/>x<b/><c n="1"/><c n="2"/><b/></a>"#.parse().unwrap();
+    e.dedup_children();
+    assert_eq!(e, r#"<a><b/>x<c n="1"/><c n="2"/><b/></a>"#.parse().unwrap());
+
+    e.dedup_children_by_name();
+    assert_eq!(e, r#"<a><b/>x<c n="1"/></a>"#.parse().unwrap());
+}
+
+#[test]
+fn test_child_count() {
+    let e: Element = "<list>x<item/><item/><other/><!-- c --><item/></list>".parse().unwrap();
+    assert_eq!(e.child_count(), 4);
+    assert_eq!(e.child_count_named("item"), 3);
+    assert_eq!(e.child_count_named("none"), 0);
+}
+
+#[test]
+fn test_position_of_child() {
+    let mut e: Element = "<a>x<b/><c/></a>".parse().unwrap();
+    let i = e.position_of_child("c").unwrap();
+    assert_eq!(i, 2);
+    assert_eq!(Some(i), e.child_position("c"));
+    e.insert_child(i, Element::new("before-c"));
+    assert_eq!(e.nth_child(1).unwrap().name, "before-c");
+    assert_eq!(e.position_of_child("d"), None);
+}
+
+#[test]
+fn test_replace_child() {
+    let mut e: Element = r#"<a><b/><c n="1"/><d/></a>"#.parse().unwrap();
+    let old = e.replace_child("c", Element::builder("c").attr("n", "2").build()).unwrap();
+    assert_eq!(old.get_attribute("n"), Some("1"));
+    assert_eq!(e, r#"<a><b/><c n="2"/><d/></a>"#.parse().unwrap());
+    assert!(e.replace_child("x", Element::new("y")).is_none());
+    assert!(e.get_child("y").is_none());
+}
+
+#[test]
+fn test_wrap_in() {
+    let e: Element = r#"<item id="1"/>"#.parse().unwrap();
+    let root = e.clone().wrap_in("root");
+    assert_eq!(root.name, "root");
+    assert_eq!(root.children, vec![XMLNode::Element(e)]);
+}
+
+#[test]
+fn test_unwrap_child() {
+    let mut e: Element = "<a><b/><wrapper>x<c/><d/></wrapper><e/></a>".parse().unwrap();
+    assert!(e.unwrap_child("wrapper"));
+    assert_eq!(e, "<a><b/>x<c/><d/><e/></a>".parse().unwrap());
+    assert!(!e.unwrap_child("wrapper"));
+
+    let item: Element = "<item/>".parse().unwrap();
+    let mut root = item.clone().wrap_in("root").wrap_in("outer");
+    assert!(root.unwrap_child("root"));
+    assert_eq!(root.children, vec![XMLNode::Element(item)]);
+}
+
+#[test]
+fn test_index_attribute() {
+    let mut e: Element = r#"<a id="1"/>"#.parse().unwrap();
+    assert_eq!(e["id"], "1");
+    e["id"] = "42".to_owned();
+    assert_eq!(e.get_attribute("id"), Some("42"));
+}
+
+#[test]
+#[should_panic(expected = "no attribute named \"missing\"")]
+fn test_index_missing_attribute() {
+    let e = Element::new("a");
+    let _ = &e["missing"];
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_json_value() {
+    let e: Element = r#"<a x="1">hi<b y="2"/><c>there</c></a>"#.parse().unwrap();
+    let v = e.to_json_value();
+    assert_eq!(
+        v,
+        serde_json::json!({
+            "name": "a",
+            "attributes": {"x": "1"},
+            "children": [
+                {"name": "b", "attributes": {"y": "2"}, "children": [], "text": null},
+                {"name": "c", "attributes": {}, "children": [], "text": "there"}
+            ],
+            "text": "hi"
+        })
+    );
+    assert_eq!(Element::from_json_value(&v).unwrap(), e);
+
+    let minimal = Element::from_json_value(&serde_json::json!({"name": "m"})).unwrap();
+    assert_eq!(minimal, Element::new("m"));
+    assert_eq!(
+        Element::from_json_value(&serde_json::json!([])),
+        Err(JsonConvertError::ExpectedObject)
+    );
+    assert_eq!(
+        Element::from_json_value(&serde_json::json!({"text": "x"})),
+        Err(JsonConvertError::MissingName)
+    );
+    assert_eq!(
+        Element::from_json_value(&serde_json::json!({"name": "a", "attributes": {"x": 1}})),
+        Err(JsonConvertError::InvalidField("attributes".to_owned()))
+    );
+}
+
+#[test]
+fn test_merge() {
+    use xmltree::merge::{merge, merge_with_config, Collision, MergeConfig, MergeError};
+
+    let base: Element = r#"<config a="1" b="2"><db host="x"><port>1</port></db><item>1</item><item>2</item></config>"#
+        .parse()
+        .unwrap();
+    let overlay: Element = r#"<config b="3"><db user="u"><port>2</port></db><item>3</item><item>4</item><item>5</item><new/></config>"#
+        .parse()
+        .unwrap();
+
+    let merged = merge(base.clone(), overlay.clone()).unwrap();
+    assert_eq!(
+        merged,
+        r#"<config a="1" b="3"><db host="x" user="u"><port>2</port></db><item>3</item><item>4</item><item>5</item><new/></config>"#
+            .parse()
+            .unwrap()
+    );
+
+    let config = MergeConfig {
+        on_collision: Collision::Replace,
+    };
+    let replaced = merge_with_config(base.clone(), overlay.clone(), &config).unwrap();
+    assert_eq!(replaced.get_child("db").unwrap().attributes.len(), 1);
+
+    let config = MergeConfig {
+        on_collision: Collision::Append,
+    };
+    let appended = merge_with_config(base.clone(), overlay.clone(), &config).unwrap();
+    assert_eq!(appended.child_count_named("db"), 2);
+    assert_eq!(appended.child_count_named("item"), 5);
+
+    let config = MergeConfig {
+        on_collision: Collision::Error,
+    };
+    assert_eq!(
+        merge_with_config(base.clone(), overlay, &config),
+        Err(MergeError::Collision("db".to_owned()))
+    );
+
+    assert!(matches!(
+        merge(base, Element::new("other")),
+        Err(MergeError::RootMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_get_text_content() {
+    let config = ParserConfig {
+        preserve_comments: true,
+        ..ParserConfig::new()
+    };
+    let data = "<p>Hello <b>bold <i>world</i></b><!-- no -->!<![CDATA[ <end>]]></p>";
+    let e = Element::parse_with_config(data.as_bytes(), &config).unwrap();
+    assert_eq!(e.get_text_content(), "Hello bold world! <end>");
+    assert_eq!(e.get_text_content_bytes(), b"Hello bold world! <end>".to_vec());
+    assert_eq!(Element::new("a").get_text_content(), "");
+}
+
+#[test]
+fn test_namespace_equal() {
+    use xmltree::diff::{deep_namespace_equal, namespace_equal};
+
+    let a: Element = r#"<dc:title xmlns:dc="urn:dc" dc:lang="en" x="1"><dc:sub>t</dc:sub></dc:title>"#.parse().unwrap();
+    let b: Element = r#"<ns1:title xmlns:ns1="urn:dc" x="1" ns1:lang="en"><ns1:sub>t</ns1:sub></ns1:title>"#.parse().unwrap();
+    assert_ne!(a, b);
+    assert!(namespace_equal(&a, &b));
+    assert!(deep_namespace_equal(&a, &b));
+
+    let c: Element = r#"<dc:title xmlns:dc="urn:other" dc:lang="en" x="1"><dc:sub>t</dc:sub></dc:title>"#.parse().unwrap();
+    assert!(!namespace_equal(&a, &c));
+
+    let d: Element = r#"<ns1:title xmlns:ns1="urn:dc" x="1" ns1:lang="en"><ns1:sub>u</ns1:sub></ns1:title>"#.parse().unwrap();
+    assert!(namespace_equal(&a, &d));
+    assert!(!deep_namespace_equal(&a, &d));
+}
+
+#[test]
+fn test_deep_equal_ignoring_whitespace() {
+    use xmltree::diff::deep_equal_ignoring_whitespace;
+
+    let a: Element = r#"<a x="1"><b>text</b><c/></a>"#.parse().unwrap();
+    let b = Element::parse(a.to_pretty_string("  ").as_bytes()).unwrap();
+    let c: Element = "<a x=\"1\">\n  <b>  text\n</b>\n  <c> </c>\n</a>".parse().unwrap();
+    assert!(deep_equal_ignoring_whitespace(&a, &b));
+    assert!(deep_equal_ignoring_whitespace(&a, &c));
+
+    let d: Element = r#"<a x="1"><b>te xt</b><c/></a>"#.parse().unwrap();
+    assert!(!deep_equal_ignoring_whitespace(&a, &d));
+    let e: Element = r#"<a x="1"><b>text</b></a>"#.parse().unwrap();
+    assert!(!deep_equal_ignoring_whitespace(&a, &e));
+}
+
+#[test]
+fn test_attrs_equal() {
+    use xmltree::diff::{attrs_equal, shallow_equal};
+
+    let a: Element = r#"<a x="1" y="2"><b z="3">one</b></a>"#.parse().unwrap();
+    let b: Element = r#"<a y="2" x="1"><b z="3">two</b></a>"#.parse().unwrap();
+    let c: Element = r#"<a y="2" x="1"><b z="4">one</b></a>"#.parse().unwrap();
+
+    // attribute order is not significant for equality
+    let reordered: Element = r#"<a y="2" x="1"><b z="3">one</b></a>"#.parse().unwrap();
+    assert_eq!(a, reordered);
+
+    assert!(shallow_equal(&a, &b) && shallow_equal(&a, &c));
+    assert!(attrs_equal(&a, &b));
+    assert!(!attrs_equal(&a, &c));
+    assert!(!shallow_equal(&a, &Element::new("a")));
+}
+
+#[test]
+fn test_select() {
+    let data = r#"<lib xmlns:x="urn:x">
+        <book id="1" lang="en"><title>A</title><author>Ann</author></book>
+        <book id="2"><title>B</title><part><title>B1</title></part></book>
+        <x:book id="3"><title>C</title></x:book>
+    </lib>"#;
+    let e: Element = data.parse().unwrap();
+
+    let names = |v: Vec<&Element>| v.iter().map(|e| e.get_text().unwrap().into_owned()).collect::<Vec<_>>();
+    assert_eq!(names(e.select("book/title").unwrap()), vec!["A", "B", "C"]);
+    assert_eq!(names(e.select("child::book/child::title").unwrap()), vec!["A", "B", "C"]);
+    assert_eq!(names(e.select("x:book/title").unwrap()), vec!["C"]);
+    assert_eq!(names(e.select("//title").unwrap()), vec!["A", "B", "B1", "C"]);
+    assert_eq!(names(e.select("book//title").unwrap()), vec!["A", "B", "B1", "C"]);
+    assert_eq!(names(e.select("descendant::part/title").unwrap()), vec!["B1"]);
+    assert_eq!(names(e.select("/lib/book[@id='2']/title").unwrap()), vec!["B"]);
+    assert_eq!(names(e.select("book[@lang]/*").unwrap()), vec!["A", "Ann"]);
+    assert_eq!(names(e.select("book[2]/title").unwrap()), vec!["B"]);
+    assert!(e.select("/other/book").unwrap().is_empty());
+    assert_eq!(e.select("//lib").unwrap().len(), 1);
+    assert_eq!(e.select(".").unwrap(), vec![&e]);
+
+    assert_eq!(e.select_one("book[@id=\"3\"]").unwrap().unwrap().qualified_name(), "x:book");
+    assert_eq!(e.select_one("nothing").unwrap(), None);
+
+    assert_eq!(e.select_values("book/@id").unwrap(), vec!["1", "2", "3"]);
+    assert_eq!(e.select_values("//@lang").unwrap(), vec!["en"]);
+    assert_eq!(e.select_values("book[2]//@id").unwrap(), vec!["2"]);
+    assert_eq!(e.select_values("@missing").unwrap(), Vec::<&str>::new());
+    assert_eq!(e.select_values("book").unwrap_err(), SelectError::MissingAttributeStep);
+    assert_eq!(e.select("book/@id").unwrap_err(), SelectError::UnexpectedAttributeStep);
+
+    assert_eq!(e.select("").unwrap_err(), SelectError::Syntax(0, "empty expression"));
+    assert!(matches!(e.select("book[@id='1'"), Err(SelectError::Syntax(12, _))));
+    assert_eq!(e.select("book[2").unwrap_err(), SelectError::Syntax(6, "expected `]`"));
+    assert!(matches!(e.select("book[0]"), Err(SelectError::Syntax(..))));
+    assert!(matches!(e.select("book/"), Err(SelectError::Syntax(5, _))));
+}
+
+#[test]
+fn test_get_text_at_path() {
+    let e: Element = "<config><database><host>localhost</host><port/></database></config>".parse().unwrap();
+    assert_eq!(e.get_text_at_path(&["database", "host"]).unwrap(), "localhost");
+    assert_eq!(e.get_text_at_path(&["database", "port"]), None);
+    assert_eq!(e.get_text_at_path(&["database", "user"]), None);
+    assert_eq!(e.get_text_at_path(&[]), None);
+}
+
+#[test]
+fn test_borrowed_element() {
+    use std::borrow::Cow;
+    use xmltree::borrowed::{BorrowedElement, BorrowedNode};
+
+    let data = r#"<?xml version="1.0"?>
+<!-- prolog -->
+<x:root xmlns:x="urn:x" a='1' b="&lt;2&gt;">
+    <item id="1">plain</item>
+    <item id="2">a &amp; b<![CDATA[<c>]]><!-- skip --><?pi skip?></item>
+    <empty/>
+</x:root>"#;
+    let e = BorrowedElement::parse(data).unwrap();
+    assert_eq!(e.name, "x:root");
+    assert_eq!(e.get_attribute("a"), Some("1"));
+    assert_eq!(e.get_attribute("b"), Some("<2>"));
+    assert!(matches!(e.attributes[1].1, Cow::Borrowed(_)));
+    assert_eq!(e.child_elements().count(), 3);
+
+    let first = e.get_child("item").unwrap();
+    assert!(matches!(first.children[0], BorrowedNode::Text(Cow::Borrowed("plain"))));
+    let second = e.child_elements().nth(1).unwrap();
+    assert_eq!(second.get_text().unwrap(), "a & b<c>");
+    assert!(e.get_child("empty").unwrap().children.is_empty());
+
+    // the owned copy matches what the regular parser produces, apart from namespaces
+    let plain = r#"<x:root xmlns:x="urn:x" a='1'><item id="1">a &amp; b<![CDATA[<c>]]></item><empty/></x:root>"#;
+    let owned = BorrowedElement::parse(plain).unwrap().to_element();
+    let mut parsed = Element::parse(plain.as_bytes()).unwrap();
+    parsed.strip_namespaces();
+    parsed.prefix = Some("x".to_owned());
+    parsed.attributes.shift_insert(0, "xmlns:x".to_owned(), "urn:x".to_owned());
+    assert_eq!(owned, parsed);
+
+    assert!(BorrowedElement::parse("<a/>trailing").is_ok());
+    for bad in &["<a>&</a>", "<a>x &foo; y</a>", "<a>&#0;</a>", "<a>&amp</a>", "<a b='&x;'/>", "<a>]]></a>"] {
+        let err = BorrowedElement::parse(bad).unwrap_err();
+        assert!(Element::parse(bad.as_bytes()).is_err(), "{}", bad);
+        assert_eq!(err.position(), Some((1, bad.find(['&', ']']).unwrap() as u64 + 1)), "{}", bad);
+    }
+    let e = BorrowedElement::parse("<a b='&#x41;&#66;'>&lt;&amp;&gt;&quot;&apos;]]</a>").unwrap();
+    assert_eq!(e.get_attribute("b"), Some("AB"));
+    assert_eq!(e.get_text().unwrap(), "<&>\"']]");
+
+    for bad in &["", "text", "<a>", "<a></b>", "<a b=1/>", "<a b='1' b='2'/>", "<a b='<'/>", "<!DOCTYPE a><a/>", "<1a/>"] {
+        assert!(BorrowedElement::parse(bad).is_err(), "{}", bad);
+    }
+    let err = BorrowedElement::parse("<a>\n  <b></c>\n</a>").unwrap_err();
+    assert_eq!(err.position(), Some((2, 8)));
+}
+
+#[test]
+fn test_path_io() {
+    let path = std::env::temp_dir().join(format!("xmltree-test-path-io-{}.xml", std::process::id()));
+    let e = Element::parse(r#"<root a="1"><child>text</child></root>"#.as_bytes()).unwrap();
+    e.write_to_path(&path).unwrap();
+    let read = Element::from_path(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read.unwrap(), e);
+
+    assert!(matches!(Element::from_path(&path), Err(ParseError::MalformedXml(_))));
+}
+
+#[test]
+fn test_default_namespace_inheritance() {
+    let e = Element::parse(
+        r#"<foo xmlns="http://example.com/"><bar><baz/></bar><other xmlns="urn:other"><inner/></other><none xmlns=""><inner/></none></foo>"#
+            .as_bytes(),
+    )
+    .unwrap();
+    let ns = |e: &Element| e.namespace.clone();
+    assert_eq!(ns(&e).as_deref(), Some("http://example.com/"));
+    let bar = e.get_child("bar").unwrap();
+    assert_eq!(ns(bar).as_deref(), Some("http://example.com/"));
+    assert_eq!(ns(bar.get_child("baz").unwrap()).as_deref(), Some("http://example.com/"));
+
+    let other = e.get_child("other").unwrap();
+    assert_eq!(ns(other).as_deref(), Some("urn:other"));
+    assert_eq!(ns(other.get_child("inner").unwrap()).as_deref(), Some("urn:other"));
+
+    let none = e.get_child("none").unwrap();
+    assert_eq!(ns(none), None);
+    assert_eq!(ns(none.get_child("inner").unwrap()), None);
+    assert_eq!(none.namespace_for_prefix(""), None);
+
+    // the default namespace does not apply to unprefixed attributes
+    let e = Element::parse(r#"<foo xmlns="urn:x" a="1"/>"#.as_bytes()).unwrap();
+    assert_eq!(e.attributes.get("a").map(String::as_str), Some("1"));
+    assert!(e.attributes_ns.is_empty());
+
+    // and the output declares the namespaces where they are needed
+    let mut out = Vec::new();
+    let e = Element::parse(
+        r#"<foo xmlns="http://example.com/"><bar/><none xmlns=""><inner/></none></foo>"#.as_bytes(),
+    )
+    .unwrap();
+    e.write(&mut out).unwrap();
+    assert_eq!(Element::parse(&out[..]).unwrap(), e);
+
+    // an element built without a namespace stays without one
+    let mut root = Element::parse(r#"<foo xmlns="urn:x"/>"#.as_bytes()).unwrap();
+    root.children.push(XMLNode::Element(Element::new("plain")));
+    let out = root.to_xml_string();
+    assert!(out.contains(r#"<plain xmlns="" />"#), "{}", out);
+    let reparsed = Element::parse(out.as_bytes()).unwrap();
+    assert_eq!(reparsed.get_child("plain").unwrap().namespace, None);
+}
+
+#[test]
+fn test_namespace_declarations() {
+    let e = Element::parse(
+        r#"<root xmlns="urn:d" xmlns:a="urn:a"><same xmlns:a="urn:a"/><child xmlns:b="urn:b" xmlns:a="urn:a2"><inner/></child><none xmlns=""/></root>"#
+            .as_bytes(),
+    )
+    .unwrap();
+
+    let decls = e.namespace_declarations();
+    assert_eq!(decls.len(), 2);
+    assert_eq!(decls[""], "urn:d");
+    assert_eq!(decls["a"], "urn:a");
+
+    // redeclaring a mapping that is already in scope declares nothing new
+    assert!(e.get_child("same").unwrap().namespace_declarations().is_empty());
+
+    let child = e.get_child("child").unwrap();
+    let decls = child.namespace_declarations();
+    assert_eq!(decls.len(), 2);
+    assert_eq!(decls["a"], "urn:a2");
+    assert_eq!(decls["b"], "urn:b");
+    assert_eq!(child.namespace_for_prefix(""), Some("urn:d"));
+    assert!(child.get_child("inner").unwrap().declared_namespaces.is_none());
+
+    let decls = e.get_child("none").unwrap().namespace_declarations();
+    assert_eq!(decls.len(), 1);
+    assert_eq!(decls[""], "");
+
+    // declarations are not significant for equality
+    let mut other = e.clone();
+    other.declared_namespaces = None;
+    assert_eq!(other, e);
+    assert!(Element::new("x").namespace_declarations().is_empty());
+}
+
+#[test]
+fn test_remove_namespace_declarations() {
+    let data = r#"<a:root xmlns:a="urn:a" xmlns:unused="urn:unused"><a:x xmlns:a="urn:a"><b:y xmlns:b="urn:b" b:attr="1"/></a:x><plain xmlns:c="urn:c"/></a:root>"#;
+    let e = Element::parse(data.as_bytes()).unwrap();
+    let min = e.with_minimized_namespaces();
+
+    let ns = min.namespaces.as_ref().unwrap();
+    assert_eq!(ns.get("a"), Some("urn:a"));
+    assert_eq!(ns.get("b"), Some("urn:b"));
+    assert_eq!(ns.get("unused"), None);
+    assert_eq!(ns.get("c"), None);
+    assert!(min.descendants().all(|d| d.namespaces.is_none() && d.declared_namespaces.is_none()));
+
+    let out = min.to_xml_string();
+    assert_eq!(out.matches("xmlns").count(), 2, "{}", out);
+    let reparsed = Element::parse(out.as_bytes()).unwrap();
+    let y = reparsed.get_child("x").unwrap().get_child("y").unwrap();
+    assert_eq!(y.namespace.as_deref(), Some("urn:b"));
+    assert_eq!(y.get_attribute_ns("attr", "urn:b"), Some("1"));
+
+    let mut e = Element::parse(r#"<root xmlns:a="urn:a"><child/></root>"#.as_bytes()).unwrap();
+    e.remove_namespace_declarations();
+    assert_eq!(e.namespaces, None);
+    assert_eq!(e.to_compact_string(), "<root><child /></root>");
+}
+
+#[test]
+fn test_namespace_declarations_round_trip() {
+    let data = r#"<p:root xmlns:p="urn:p" xmlns:q="urn:q"><p:item q:id="1">one</p:item><sub xmlns="urn:d"><leaf/></sub></p:root>"#;
+    let mut e = Element::parse(data.as_bytes()).unwrap();
+
+    // a new element carrying the parent's mappings, with a prefix declared only there
+    let mut added = Element::new("added");
+    added.prefix = Some("r".to_owned());
+    added.namespace = Some("urn:r".to_owned());
+    let mut ns = e.namespaces.clone().unwrap();
+    ns.put("r", "urn:r");
+    added.namespaces = Some(ns);
+    e.children.push(XMLNode::Element(added));
+    e.get_mut_child("item").unwrap().set_text("two");
+
+    let out = e.to_xml_string();
+    assert!(out.contains(r#"<p:root xmlns:p="urn:p" xmlns:q="urn:q">"#), "{}", out);
+    assert!(out.contains(r#"<r:added xmlns:r="urn:r" />"#), "{}", out);
+    let reparsed = Element::parse(out.as_bytes()).unwrap();
+    assert_eq!(reparsed, e);
+}
+
+#[test]
+fn test_copy_attributes_from() {
+    let source = Element::parse(r#"<s a="1" b="2" c="3"/>"#.as_bytes()).unwrap();
+
+    let mut e = Element::parse(r#"<e b="old" d="4"/>"#.as_bytes()).unwrap();
+    e.copy_attributes_from(&source);
+    assert_eq!(e.to_compact_string(), r#"<e b="old" d="4" a="1" c="3" />"#);
+
+    let mut e = Element::parse(r#"<e b="old" d="4"/>"#.as_bytes()).unwrap();
+    e.merge_attributes_from(&source);
+    assert_eq!(e.to_compact_string(), r#"<e b="2" d="4" a="1" c="3" />"#);
+}
+
+#[test]
+fn test_iter_attributes_sorted() {
+    let e = Element::parse(r#"<e c="3" a="1" b="2"/>"#.as_bytes()).unwrap();
+    let sorted: Vec<_> = e.iter_attributes_sorted().collect();
+    assert_eq!(sorted, vec![("a", "1"), ("b", "2"), ("c", "3")]);
+    assert_eq!(Element::new("e").iter_attributes_sorted().count(), 0);
+}
+
+#[test]
+fn test_attribute_names() {
+    let e = Element::parse(r#"<e id="1" name="x" xmlns:a="urn:a" a:ns="y"/>"#.as_bytes()).unwrap();
+    assert_eq!(e.attribute_names().collect::<Vec<_>>(), vec!["id", "name"]);
+
+    assert!(e.has_all_attributes(&["name", "id"]));
+    assert!(e.has_all_attributes(&[]));
+    assert!(!e.has_all_attributes(&["id", "ns"]));
+
+    assert_eq!(e.missing_attributes(&["type", "id", "size"]), vec!["type", "size"]);
+    assert!(e.missing_attributes(&["id"]).is_empty());
+}
+
+#[test]
+fn test_retain_attributes() {
+    let mut e = Element::parse(
+        r#"<a href="x" onclick="evil()" title="t" onload="evil()" data-id="1" xmlns:on="urn:on" on:x="kept"/>"#.as_bytes(),
+    )
+    .unwrap();
+    e.remove_attributes_with_prefix("on");
+    assert_eq!(e.attribute_names().collect::<Vec<_>>(), vec!["href", "title", "data-id"]);
+    assert_eq!(e.get_attribute_ns("x", "urn:on"), Some("kept"));
+
+    e.retain_attributes(|k, v| k != "title" && v != "1");
+    assert_eq!(e.attribute_names().collect::<Vec<_>>(), vec!["href"]);
+}
+
+#[test]
+fn test_rename_attribute() {
+    let mut e = Element::parse(r#"<e a="1" b="2" c="3"/>"#.as_bytes()).unwrap();
+    assert!(e.rename_attribute("b", "x", false));
+    assert_eq!(e.to_compact_string(), r#"<e a="1" x="2" c="3" />"#);
+
+    assert!(!e.rename_attribute("missing", "y", true));
+    assert!(!e.rename_attribute("c", "a", false));
+    assert_eq!(e.to_compact_string(), r#"<e a="1" x="2" c="3" />"#);
+
+    assert!(e.rename_attribute("c", "a", true));
+    assert_eq!(e.to_compact_string(), r#"<e x="2" a="3" />"#);
+    assert!(e.rename_attribute("x", String::from("x"), false));
+    assert_eq!(e.get_attribute("x"), Some("2"));
+}
+
+#[test]
+fn test_get_attribute_or() {
+    let e = Element::parse(r#"<e a="1"/>"#.as_bytes()).unwrap();
+    assert_eq!(e.get_attribute_or("a", "default"), "1");
+    assert_eq!(e.get_attribute_or("b", "default"), "default");
+
+    let fallback = String::from("computed");
+    assert_eq!(e.get_attribute_or_else("a", || unreachable!()), "1");
+    assert_eq!(e.get_attribute_or_else("b", || &fallback), "computed");
+}
+
+#[test]
+fn test_write_config() {
+    let e = Element::parse("<root><a>x</a><code>if a &lt; b</code></root>".as_bytes()).unwrap();
+
+    let mut buf = Vec::new();
+    e.write_with_config(&mut buf, WriteConfig::new().indent(4).no_declaration().cdata_sections(&["code"]))
+        .unwrap();
+    let s = String::from_utf8(buf).unwrap();
+    assert_eq!(
+        s,
+        "<root>\n    <a>x</a>\n    <code><![CDATA[if a < b]]></code>\n</root>"
+    );
+    assert_eq!(Element::parse(s.as_bytes()).unwrap(), {
+        let mut expected = e.clone();
+        expected.children[1].as_mut_element().unwrap().children = vec![XMLNode::CData("if a < b".to_owned())];
+        expected
+    });
+
+    // text that cannot be a CDATA section is left as it is
+    let e2 = Element::parse("<code>]]&gt;</code>".as_bytes()).unwrap();
+    let mut buf = Vec::new();
+    e2.write_with_config(&mut buf, WriteConfig::new().cdata_sections(&["code"])).unwrap();
+    assert!(!String::from_utf8(buf).unwrap().contains("CDATA"));
+
+    // an EmitterConfig is still accepted, and the default matches `write`
+    let mut buf = Vec::new();
+    e.write_with_config(&mut buf, EmitterConfig::new().write_document_declaration(false)).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), e.to_compact_string());
+    let mut a = Vec::new();
+    let mut b = Vec::new();
+    e.write(&mut a).unwrap();
+    e.write_with_config(&mut b, WriteConfig::default()).unwrap();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn test_to_events() {
+    use xml::writer::events::XmlEvent;
+
+    let e = Element::parse(r#"<root a="1"><child>text</child><![CDATA[data]]></root>"#.as_bytes()).unwrap();
+    let events = e.to_events();
+    assert_eq!(events.len(), 6);
+    match events[0] {
+        XmlEvent::StartElement { ref name, ref attributes, .. } => {
+            assert_eq!(name.local_name, "root");
+            assert_eq!(attributes.len(), 1);
+            assert_eq!(attributes[0].value, "1");
+        }
+        ref other => panic!("unexpected event {:?}", other),
+    }
+    assert!(matches!(events[1], XmlEvent::StartElement { ref name, .. } if name.local_name == "child"));
+    assert!(matches!(events[2], XmlEvent::Characters("text")));
+    assert!(matches!(events[3], XmlEvent::EndElement { name: Some(ref name) } if name.local_name == "child"));
+    assert!(matches!(events[4], XmlEvent::CData("data")));
+    assert!(matches!(events[5], XmlEvent::EndElement { name: Some(ref name) } if name.local_name == "root"));
+
+    // writing the events produces the same output as writing the element
+    let mut buf = Vec::new();
+    {
+        let mut writer = EmitterConfig::new().write_document_declaration(false).create_writer(&mut buf);
+        for event in e.to_events() {
+            writer.write(event).unwrap();
+        }
+    }
+    assert_eq!(String::from_utf8(buf).unwrap(), e.to_compact_string());
+}
+
+#[test]
+fn test_borrowed_element_max_depth() {
+    use xmltree::borrowed::BorrowedElement;
+
+    let deep = "<a>".repeat(200000);
+    let err = BorrowedElement::parse(&deep).unwrap_err();
+    assert!(err.to_string().contains("nesting too deep"), "{}", err);
+
+    let limit = format!("{}{}", "<a>".repeat(256), "</a>".repeat(256));
+    let e = BorrowedElement::parse(&limit).unwrap();
+    assert_eq!(e.to_element().max_depth(), 256);
+    let over = format!("{}{}", "<a>".repeat(257), "</a>".repeat(257));
+    assert!(BorrowedElement::parse(&over).is_err());
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn test_parse_async() {
+    let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+
+    // longer than a single read
+    let data = format!("<list>{}</list>", "<item>x</item>".repeat(2000));
+    let e = rt.block_on(Element::parse_async(data.as_bytes())).unwrap();
+    assert_eq!(e, Element::parse(data.as_bytes()).unwrap());
+    assert_eq!(e.child_count(), 2000);
+
+    let err = rt.block_on(Element::parse_async(&b"<a><b></a>"[..])).unwrap_err();
+    assert!(matches!(err, ParseError::MalformedXml(_)));
+}